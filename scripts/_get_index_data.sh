@@ -34,7 +34,21 @@ install_tool "go-enricher" "https://bin.pkgforge.dev/$(uname -m)-$(uname -s)/go-
 #-------------------------------------------------------#
 ##Generate Dump: https://pkg.go.dev/about
  #https://index.golang.org/index
- go-indexer --start-date "${CUTOFF_DATE}" --output "${TEMP_DIR}/INDEX.jsonl" --verbose
+ #Checkpoints outlive TEMP_DIR (cached by the workflow) so a cancelled run resumes where it stopped
+ GO_INDEXER_STATE="${GO_INDEXER_STATE:-${HOME}/.cache/go-indexer/state}"
+ mkdir -p "${GO_INDEXER_STATE}/days"
+ #Drop days before the cutoff, they would otherwise be exported again
+ find "${GO_INDEXER_STATE}/days" -maxdepth 1 -type f -name 'day_*' | while read -r DAY_FILE; do
+   DAY="$(basename "${DAY_FILE}" | cut -d. -f1 | sed 's/^day_//')"
+   [[ "${DAY}" < "${CUTOFF_DATE//-/_}" ]] && rm -f "${DAY_FILE}"
+ done
+ go-indexer --start-date "${CUTOFF_DATE}" --output "${TEMP_DIR}/INDEX.jsonl" --verbose \
+   --state-dir "${GO_INDEXER_STATE}" \
+   --include-host "bitbucket.org" --include-host "buildroot.net" --include-host "codeberg.org" \
+   --include-host "gitee.com" --include-host "github.com" --include-host "gitlab.com" \
+   --include-host "sr.ht" --include-host "sourceforge.net" \
+   --exclude-path-regex '(?i)^6github\.com|go-micro\.|kubevirt\.|opentelemetry\.|staging\.|www\.|\.git$' \
+   --exclude-internal --emit enricher
  echo -e "\n[+] Processing RAW Packages\n"
 ##Process
  jq --arg cutoff_date "${CUTOFF_DATE}" \
@@ -42,27 +56,13 @@ install_tool "go-enricher" "https://bin.pkgforge.dev/$(uname -m)-$(uname -s)/go-
   def sanitize: if type == "string" then gsub("[`${}\\\\\"'\''();|&<>]"; "_") else . end;
   .[] 
   | select(
-      (.source | ascii_downcase | test("^6github\\.com|go-micro\\.|kubevirt\\.|opentelemetry\\.|staging\\.|www\\.") | not)
-      and
-      (.source | ascii_downcase | endswith(".git") | not)
-      and
-      (.source | ascii_downcase | ltrimstr(" ") | test("^(bitbucket\\.org/|buildroot\\.net/|codeberg\\.org/|gitee\\.com/|github\\.com/|gitlab\\.com/|sr\\.ht/|sourceforge\\.net/)"))
-      and
-      (
-        (.versions[-1] | split("-") | length != 3)
-        or
-        (.versions[-1] | split("-")[1] | .[0:8] | test("^\\d{8}$") and . >= ($cutoff_date[0:8]))
-      )
+      (.version | split("-") | length != 3)
+      or
+      (.version | split("-")[1] | .[0:8] | test("^\\d{8}$") and . >= ($cutoff_date[0:8]))
     ) 
-  | {
-      source: .source, 
-      version: .versions[-1],
-      pkg_id: ((.source // "") | sub("^https?://"; "") | gsub("[^a-zA-Z0-9.-]"; "_")) | sanitize,
-      download: ("https://proxy.golang.org/" + .source + "/@v/" + .versions[-1] + ".zip")
+  | . + {
+      pkg_id: ((.source // "") | sub("^https?://"; "") | gsub("[^a-zA-Z0-9.-]"; "_")) | sanitize
     }
-  | select(
-      (.download | test("/test/@v/|/tests/@v/|/example/@v/|/examples/@v/|/vendor/@v/") | not)
-  )
  ' "${TEMP_DIR}/INDEX.processed.json" > "${TEMP_DIR}/RAW.json.tmp"
 ##Merge
  awk '/^\s*{\s*$/{flag=1; buffer="{\n"; next} /^\s*}\s*$/{if(flag){buffer=buffer"}\n"; print buffer}; flag=0; next} flag{buffer=buffer$0"\n"}' "${TEMP_DIR}/RAW.json.tmp" | jq -c '. as $line | (fromjson? | .message) // $line' >> "${TEMP_DIR}/RAW.json.raw"
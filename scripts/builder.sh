@@ -237,16 +237,23 @@ install_tool "go-detector" "https://bin.pkgforge.dev/$(uname -m)-$(uname -s)/go-
    #Env  
     echo -e "\n[+] Target: ${GO_TARGET}\n"
     mkdir -p "${G_ARTIFACT_DIR}"
-   #Get Cmds
-    mapfile -t "GO_CMD_DIRS" < <(go list -f '{{if eq .Name "main"}}{{.Dir}}{{end}}' ./... 2>/dev/null |\
-     awk -v pwd="$(pwd)" '
-     /^[ \t]*$/ { next }
-     {
-         gsub(/^[ \t\r\n]+|[ \t\r\n]+$/, "")  # Trim whitespace
-         if ($0 == pwd) print "./"
-         else if (index($0, pwd "/") == 1) print "./" substr($0, length(pwd) + 2)
-         else print $0
-     }')
+   #Get Cmds (Prefer the go install targets documented in the README)
+    GO_CMD_DIRS=()
+    if command -v go-detector &>/dev/null; then
+      mapfile -t "GO_CMD_DIRS" < <(go-detector --quiet --json "$(pwd)" 2>/dev/null |\
+       jq -r '.install_targets[]?.dirs[]?' 2>/dev/null | sort -u)
+    fi
+    if [[ ${#GO_CMD_DIRS[@]} -le 0 ]]; then
+     mapfile -t "GO_CMD_DIRS" < <(go list -f '{{if eq .Name "main"}}{{.Dir}}{{end}}' ./... 2>/dev/null |\
+      awk -v pwd="$(pwd)" '
+      /^[ \t]*$/ { next }
+      {
+          gsub(/^[ \t\r\n]+|[ \t\r\n]+$/, "")  # Trim whitespace
+          if ($0 == pwd) print "./"
+          else if (index($0, pwd "/") == 1) print "./" substr($0, length(pwd) + 2)
+          else print $0
+      }')
+    fi
      if [[ ${#GO_CMD_DIRS[@]} -le 0 ]]; then
         echo -e "\n[✗] FATAL: Failed to find any CMD Dirs\n" >&2
         build_fail_gh
@@ -0,0 +1,560 @@
+use crate::deps::Dependencies;
+use crate::error::{DetectError, ErrorKind, Stage};
+use crate::hints::BuildHint;
+use crate::index::{is_example_file, RepoIndex};
+use crate::install::InstallTarget;
+use crate::workflows::WorkflowJob;
+use crate::Detector;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectType {
+    Cli,
+    Library,
+    Unclear,
+    Collection,
+}
+
+impl ProjectType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectType::Cli => "cli",
+            ProjectType::Library => "library",
+            ProjectType::Unclear => "unclear",
+            ProjectType::Collection => "collection",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ProjectType::Cli => 0,
+            ProjectType::Library => 1,
+            ProjectType::Unclear => 2,
+            ProjectType::Collection => 5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Analysis {
+    pub main_packages: usize,
+    pub directory_score: i32,
+    pub readme_score: i32,
+    pub executable_score: i32,
+    pub go_mod_score: i32,
+    pub binary_score: i32,
+    pub collection_score: i32,
+    pub total_score: i32,
+    pub project_type: ProjectType,
+    pub confidence: &'static str,
+    pub details: Vec<String>,
+    pub build_hints: Vec<BuildHint>,
+    /// `go install` commands from the README, the maintainer-blessed entry points
+    pub install_targets: Vec<InstallTarget>,
+    /// CI jobs that build binaries, with their target platforms
+    pub workflows: Vec<WorkflowJob>,
+    pub dependencies: Dependencies,
+}
+
+impl Detector {
+    fn check_main_packages(&self, index: &RepoIndex) -> (usize, Vec<PathBuf>, Vec<String>) {
+        self.log_verbose("Checking for main packages...");
+
+        let mut main_count = 0;
+        let mut main_dirs = Vec::new();
+        let mut details = Vec::new();
+
+        for info in index.go_files.iter().filter(|f| !is_example_file(&f.path)) {
+            if info.package_name == "main" && info.has_main_func {
+                if let Some(parent) = info.path.parent() {
+                    main_dirs.push(parent.to_path_buf());
+                }
+                let relative_path = info.path.to_string_lossy();
+
+                self.log_verbose(&format!(
+                    "Found main package with main(): {}",
+                    relative_path
+                ));
+                details.push(format!("Main package: {}", relative_path));
+                main_count += 1;
+
+                // Use CLI patterns for additional scoring
+                if info.has_cli_patterns {
+                    details.push(format!("CLI patterns detected in: {}", relative_path));
+                }
+
+                for import in &info.imports {
+                    if import.contains("cobra")
+                        || import.contains("urfave/cli")
+                        || import.contains("kingpin")
+                    {
+                        details.push(format!("CLI framework import: {}", import));
+                    }
+                }
+            }
+        }
+        (main_count, main_dirs, details)
+    }
+
+    fn check_directory_structure(&self, index: &RepoIndex) -> (i32, Vec<String>) {
+        self.log_verbose("Analyzing directory structure...");
+        let mut score = 0;
+        let mut details = Vec::new();
+
+        // Check for CLI-specific directories
+        if index.has_dir("cmd") {
+            self.log_verbose("Found 'cmd/' directory (+4 points)");
+            score += 4;
+            details.push("CLI structure: cmd/ directory".to_string());
+        }
+
+        if index.has_file("main.go") {
+            self.log_verbose("Found 'main.go' in root (+3 points)");
+            score += 3;
+            details.push("Entry point: main.go in root".to_string());
+        }
+
+        // Check for CLI binary directories
+        if index.has_dir("bin") {
+            score += 1;
+            details.push("Binary directory: bin/".to_string());
+        }
+
+        // Check for library-specific structure
+        let has_lib_dirs =
+            index.has_dir("pkg") || index.has_dir("lib") || index.has_dir("internal");
+
+        if has_lib_dirs && !index.has_file("main.go") && !index.has_dir("cmd") {
+            self.log_verbose("Library structure without main entry points (-2 points)");
+            score -= 2;
+            details.push("Library structure: pkg/lib/internal without main".to_string());
+        }
+
+        // Check for Makefile or build scripts
+        if index.has_file("Makefile") || index.has_file("build.sh") {
+            score += 1;
+            details.push("Build system present".to_string());
+        }
+
+        (score, details)
+    }
+
+    fn check_go_mod(&self, index: &RepoIndex) -> (i32, Vec<String>) {
+        self.log_verbose("Analyzing go.mod...");
+        let mut score = 0;
+        let mut details = Vec::new();
+
+        if let Some(content) = index.go_mod() {
+            // Check for CLI frameworks
+            let cli_deps = [
+                "github.com/spf13/cobra",
+                "github.com/urfave/cli",
+                "github.com/spf13/pflag",
+                "gopkg.in/alecthomas/kingpin",
+                "github.com/jessevdk/go-flags",
+            ];
+
+            for dep in &cli_deps {
+                if content.contains(dep) {
+                    score += 2;
+                    details.push(format!("CLI dependency: {}", dep));
+                    self.log_verbose(&format!("Found CLI dependency: {} (+2 points)", dep));
+                }
+            }
+
+            // Check module name pattern
+            if let Some(line) = content.lines().find(|l| l.starts_with("module ")) {
+                let module_name = line.strip_prefix("module ").unwrap_or("").trim();
+                if module_name.ends_with("/cmd")
+                    || module_name.contains("-cli")
+                    || module_name.contains("tool")
+                {
+                    score += 1;
+                    details.push("CLI-pattern module name".to_string());
+                }
+            }
+        }
+
+        (score, details)
+    }
+
+    fn check_readme(&self, index: &RepoIndex) -> (i32, Vec<String>) {
+        self.log_verbose("Analyzing README...");
+        let mut score = 0;
+        let mut details = Vec::new();
+
+        if let Some(content) = index.readme() {
+            let content_lower = content.to_lowercase();
+
+            // Installation patterns
+            if content_lower.contains("go install") && content_lower.contains("@latest") {
+                self.log_verbose("Found binary installation instructions (+3 points)");
+                score += 3;
+                details.push("Installation: go install command".to_string());
+            }
+
+            if content_lower.contains("go get") && !content_lower.contains("import") {
+                score += 2;
+                details.push("Installation: go get command".to_string());
+            }
+
+            // CLI keywords
+            let cli_keywords = [
+                "cli tool",
+                "command line",
+                "command-line",
+                "terminal",
+                "console",
+            ];
+            for keyword in &cli_keywords {
+                if content_lower.contains(keyword) {
+                    score += 1;
+                    details.push(format!("CLI keyword: {}", keyword));
+                    break;
+                }
+            }
+
+            // Usage examples
+            if self.usage_regex.is_match(content) {
+                self.log_verbose("Found command-line usage examples (+2 points)");
+                score += 2;
+                details.push("Usage: Command-line examples".to_string());
+            }
+
+            // Options/flags documentation
+            if content.contains("--") || content.contains("flags:") || content.contains("options:")
+            {
+                score += 1;
+                details.push("Documentation: CLI flags/options".to_string());
+            }
+
+            // Library indicators
+            if content_lower.contains("import") && content_lower.contains("package") {
+                score -= 1;
+                details.push("Library indicator: import examples".to_string());
+            }
+        }
+
+        (score, details)
+    }
+
+    fn check_executable_indicators(
+        &self,
+        index: &RepoIndex,
+        deps: &Dependencies,
+    ) -> (i32, Vec<String>) {
+        self.log_verbose("Checking for executable indicators...");
+        let mut score = 0;
+        let mut details = Vec::new();
+
+        let mut cli_patterns_found = HashSet::new();
+
+        for info in index.go_files.iter().filter(|f| !is_example_file(&f.path)) {
+            // Use the has_cli_patterns field
+            if info.has_cli_patterns && !cli_patterns_found.contains("cli_patterns") {
+                self.log_verbose("Found CLI patterns in file (+2 points)");
+                score += 2;
+                details.push("CLI patterns detected in source".to_string());
+                cli_patterns_found.insert("cli_patterns");
+            }
+
+            // Use imports for specific framework detection
+            for import in &info.imports {
+                let framework = if import.contains("cobra") {
+                    "Cobra CLI framework"
+                } else if import.contains("urfave/cli") {
+                    "Urfave CLI framework"
+                } else if import.contains("kingpin") {
+                    "Kingpin CLI framework"
+                } else if import.contains("flag") {
+                    "Standard flag package"
+                } else {
+                    continue;
+                };
+
+                // Third-party imports only count when the module is really in the build
+                let third_party = import.split('/').next().is_some_and(|h| h.contains('.'));
+                if third_party && deps.is_known() && !deps.provides(import) {
+                    self.log_verbose(&format!(
+                        "Ignoring {}: {} is not in the dependency graph",
+                        framework, import
+                    ));
+                    continue;
+                }
+
+                if !cli_patterns_found.contains(framework) {
+                    self.log_verbose(&format!("Found {}: {} (+1 point)", framework, import));
+                    score += 1;
+                    details.push(format!("CLI framework: {}", framework));
+                    cli_patterns_found.insert(framework);
+                }
+            }
+        }
+
+        (score, details)
+    }
+
+    fn check_binary_indicators(&self, index: &RepoIndex) -> (i32, Vec<String>) {
+        self.log_verbose("Checking for binary indicators...");
+        let mut score = 0;
+        let mut details = Vec::new();
+
+        // Check for release files
+        if index.has_file("goreleaser.yml") || index.has_file(".goreleaser.yml") {
+            score += 2;
+            details.push("Release: GoReleaser config".to_string());
+        }
+
+        // Check for Dockerfile
+        if let Some(content) = index.text("Dockerfile") {
+            if content.contains("ENTRYPOINT") || content.contains("CMD") {
+                score += 1;
+                details.push("Container: Executable Docker image".to_string());
+            }
+        }
+
+        (score, details)
+    }
+
+    fn check_collection_indicators(
+        &self,
+        index: &RepoIndex,
+        main_dirs: &[PathBuf],
+    ) -> (i32, Vec<String>) {
+        self.log_verbose("Checking for tutorial/exercise collection indicators...");
+        let mut score = 0;
+        let mut details = Vec::new();
+
+        let unique_dirs: HashSet<&PathBuf> = main_dirs.iter().collect();
+
+        // Many independent programs is the strongest hint
+        if unique_dirs.len() >= 15 {
+            self.log_verbose(&format!(
+                "Found {} main package directories (+3 points)",
+                unique_dirs.len()
+            ));
+            score += 3;
+            details.push(format!("Collection: {} main packages", unique_dirs.len()));
+        } else if unique_dirs.len() >= 5 {
+            self.log_verbose(&format!(
+                "Found {} main package directories (+2 points)",
+                unique_dirs.len()
+            ));
+            score += 2;
+            details.push(format!("Collection: {} main packages", unique_dirs.len()));
+        }
+
+        // Numbered lesson/exercise directories (00-hello, 0.enum, day-03 ...)
+        let numbered = unique_dirs
+            .iter()
+            .filter(|dir| {
+                dir.components().any(|c| {
+                    c.as_os_str()
+                        .to_str()
+                        .is_some_and(|name| self.numbered_dir_regex.is_match(name))
+                })
+            })
+            .count();
+        if numbered >= 3 && numbered * 2 >= unique_dirs.len() {
+            self.log_verbose(&format!(
+                "Found {} numbered main package directories (+3 points)",
+                numbered
+            ));
+            score += 3;
+            details.push(format!("Collection: {} numbered directories", numbered));
+        }
+
+        // Keywords in module path, top-level directories or README title
+        let mut haystack = String::new();
+        if let Some(content) = index.go_mod() {
+            if let Some(line) = content.lines().find(|l| l.starts_with("module ")) {
+                haystack.push_str(line);
+                haystack.push('\n');
+            }
+        }
+        for entry in index.top_level() {
+            haystack.push_str(&entry.to_string_lossy());
+            haystack.push('\n');
+        }
+        if let Some(readme) = index.readme() {
+            for line in readme.lines().filter(|l| !l.trim().is_empty()).take(3) {
+                haystack.push_str(line);
+                haystack.push('\n');
+            }
+        }
+        if let Some(keyword) = find_collection_keyword(&haystack) {
+            self.log_verbose(&format!(
+                "Found collection keyword: {} (+2 points)",
+                keyword
+            ));
+            score += 2;
+            details.push(format!("Collection keyword: {}", keyword));
+        }
+
+        // Loose programs without a root module
+        if index.go_mod().is_none() && unique_dirs.len() > 1 {
+            self.log_verbose("Multiple main packages without root go.mod (+1 point)");
+            score += 1;
+            details.push("Collection: no root go.mod".to_string());
+        }
+
+        (score, details)
+    }
+
+    pub(crate) fn analyze_repo(&self, repo_dir: &Path) -> Result<Analysis> {
+        let index = self.build_index(repo_dir)?;
+
+        // Check if it's a Go project
+        if index.go_mod().is_none() && index.go_files.is_empty() {
+            return Err(DetectError::new(
+                ErrorKind::NotGoProject,
+                Stage::Analyze,
+                "Not a Go project (no go.mod or .go files found)",
+            )
+            .into());
+        }
+
+        let (main_packages, main_dirs, mut all_details) = self.check_main_packages(&index);
+        let (directory_score, mut dir_details) = self.check_directory_structure(&index);
+        let (readme_score, mut readme_details) = self.check_readme(&index);
+        let (mut install_details, install_targets) = self.check_install_targets(&index, &main_dirs);
+        let (mut dep_details, dependencies) = self.check_dependencies(&index);
+        let (executable_score, mut exec_details) =
+            self.check_executable_indicators(&index, &dependencies);
+        let (go_mod_score, mut mod_details) = self.check_go_mod(&index);
+        let (mut binary_score, mut bin_details) = self.check_binary_indicators(&index);
+        let (hint_score, mut hint_details, mut build_hints) = self.check_build_hints(&index);
+        binary_score += hint_score;
+        let (ci_score, mut ci_details, workflows, ci_hints) = self.check_workflows(&index);
+        binary_score += ci_score;
+        build_hints.extend(ci_hints);
+        let (collection_score, mut collection_details) =
+            self.check_collection_indicators(&index, &main_dirs);
+
+        all_details.append(&mut dir_details);
+        all_details.append(&mut readme_details);
+        all_details.append(&mut install_details);
+        all_details.append(&mut exec_details);
+        all_details.append(&mut mod_details);
+        all_details.append(&mut bin_details);
+        all_details.append(&mut hint_details);
+        all_details.append(&mut ci_details);
+        all_details.append(&mut dep_details);
+        all_details.append(&mut collection_details);
+
+        let total_score = (main_packages as i32) * 5
+            + directory_score
+            + readme_score
+            + executable_score
+            + go_mod_score
+            + binary_score;
+
+        // Collections are checked first as they usually carry many main packages
+        let (project_type, confidence) = if collection_score >= 7 {
+            (ProjectType::Collection, "HIGH")
+        } else if collection_score >= 5 {
+            (ProjectType::Collection, "MEDIUM")
+        } else if main_packages > 0 || total_score >= 6 {
+            (ProjectType::Cli, "HIGH")
+        } else if total_score >= 3 {
+            (ProjectType::Cli, "MEDIUM")
+        } else if total_score <= -3 {
+            (ProjectType::Library, "HIGH")
+        } else if total_score <= 0 {
+            (ProjectType::Library, "MEDIUM")
+        } else {
+            (ProjectType::Unclear, "LOW")
+        };
+
+        Ok(Analysis {
+            main_packages,
+            directory_score,
+            readme_score,
+            executable_score,
+            go_mod_score,
+            binary_score,
+            collection_score,
+            total_score,
+            project_type,
+            confidence,
+            details: all_details,
+            build_hints,
+            install_targets,
+            workflows,
+            dependencies,
+        })
+    }
+}
+
+/// Keywords naming tutorial/exercise collections, as runs of words
+const COLLECTION_KEYWORDS: &[&str] = &[
+    "leetcode",
+    "exercism",
+    "exercises",
+    "learning",
+    "learn-go",
+    "learngo",
+    "tutorial",
+    "gobyexample",
+    "go-by-example",
+    "kata",
+    "advent-of-code",
+    "adventofcode",
+    "homework",
+    "course",
+    "practice",
+    "algorithms",
+    "interview",
+];
+
+/// First collection keyword found as whole words, so that `course` matches
+/// `go-course` but not `resource` and `learning` not `machinelearningkit`
+fn find_collection_keyword(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    COLLECTION_KEYWORDS.iter().copied().find(|keyword| {
+        let keyword: Vec<&str> = keyword.split('-').collect();
+        words
+            .windows(keyword.len())
+            .any(|window| window.iter().zip(&keyword).all(|(w, k)| w == k))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords_match_whole_words() {
+        assert_eq!(
+            find_collection_keyword("module github.com/someone/go-course"),
+            Some("course")
+        );
+        assert_eq!(
+            find_collection_keyword("# My LeetCode solutions"),
+            Some("leetcode")
+        );
+        assert_eq!(
+            find_collection_keyword("advent_of_code_2023"),
+            Some("advent-of-code")
+        );
+        assert_eq!(find_collection_keyword("learn-go/"), Some("learn-go"));
+        assert_eq!(find_collection_keyword("Exercises"), Some("exercises"));
+    }
+
+    #[test]
+    fn keywords_inside_words_are_ignored() {
+        assert_eq!(find_collection_keyword("module example.com/resource"), None);
+        assert_eq!(find_collection_keyword("discourse-bot"), None);
+        assert_eq!(find_collection_keyword("algorithmsx"), None);
+        assert_eq!(find_collection_keyword("interviewer"), None);
+        assert_eq!(find_collection_keyword("go-by-examples"), None);
+    }
+}
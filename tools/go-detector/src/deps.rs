@@ -0,0 +1,373 @@
+use crate::index::RepoIndex;
+use crate::Detector;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Modules known to need cgo and system libraries, which breaks static builds.
+/// Matched as a module path prefix, so `/v2` and sub-modules are covered.
+const STATIC_BUILD_RISKS: &[(&str, &str)] = &[
+    ("github.com/mattn/go-sqlite3", "SQLite via cgo"),
+    ("github.com/gotk3/gotk3", "GTK3 bindings"),
+    ("github.com/diamondburned/gotk4", "GTK4 bindings"),
+    ("github.com/proglottis/gpgme", "links libgpgme"),
+    ("github.com/mtrmac/gpgme", "links libgpgme"),
+    ("libvirt.org/go/libvirt", "links libvirt"),
+    ("github.com/libvirt/libvirt-go", "links libvirt"),
+    ("github.com/google/gopacket", "gopacket/pcap links libpcap"),
+    ("github.com/go-gl/glfw", "GLFW/OpenGL via cgo"),
+    ("github.com/go-gl/gl", "OpenGL via cgo"),
+    ("fyne.io/fyne", "GLFW/OpenGL via cgo"),
+    (
+        "github.com/hajimehoshi/ebiten",
+        "graphics and audio via cgo",
+    ),
+    ("github.com/veandco/go-sdl2", "links SDL2"),
+    ("github.com/webview/webview", "links WebKitGTK"),
+    ("github.com/therecipe/qt", "Qt bindings"),
+    ("github.com/go-vgo/robotgo", "links X11 and libpng"),
+    (
+        "github.com/confluentinc/confluent-kafka-go",
+        "links librdkafka",
+    ),
+    ("github.com/tecbot/gorocksdb", "links RocksDB"),
+    ("github.com/linxGnu/grocksdb", "links RocksDB"),
+    ("github.com/ceph/go-ceph", "links librados"),
+    ("github.com/miekg/pkcs11", "loads PKCS#11 modules via cgo"),
+    ("github.com/seccomp/libseccomp-golang", "links libseccomp"),
+    ("github.com/DataDog/zstd", "zstd via cgo"),
+    ("github.com/gographics/imagick", "links ImageMagick"),
+    ("gocv.io/x/gocv", "links OpenCV"),
+    ("github.com/tensorflow/tensorflow", "links libtensorflow"),
+];
+
+/// A dependency from the list above that ends up in the build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyRisk {
+    pub module: String,
+    pub version: String,
+    /// Required directly by the main module rather than pulled in transitively
+    pub direct: bool,
+    pub reason: &'static str,
+}
+
+/// Summary of the module dependency graph
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Dependencies {
+    /// Module path declared in go.mod
+    pub module: Option<String>,
+    /// Modules required directly by the main module
+    pub direct: usize,
+    /// Modules in the build list, direct and transitive
+    pub total: usize,
+    /// Whether `vendor/modules.txt` was used
+    pub vendored: bool,
+    pub risks: Vec<DependencyRisk>,
+    #[serde(skip)]
+    modules: BTreeMap<String, Module>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Module {
+    version: String,
+    direct: bool,
+    /// Contents are needed to build, not just its go.mod for version selection
+    built: bool,
+    /// `replace` target, if any
+    replacement: Option<String>,
+}
+
+impl Dependencies {
+    /// Whether a go.mod was found, i.e. the graph can be trusted to be complete
+    pub(crate) fn is_known(&self) -> bool {
+        self.module.is_some()
+    }
+
+    /// Whether an import path is provided by the main module or a built dependency
+    pub(crate) fn provides(&self, import: &str) -> bool {
+        let within = |module: &str| import == module || import.starts_with(&format!("{}/", module));
+        self.module.as_deref().is_some_and(within)
+            || self
+                .modules
+                .iter()
+                .any(|(path, module)| module.built && within(path))
+    }
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim().trim_matches('"').trim_matches('`')
+}
+
+/// Parse `require` and `replace` directives, both single-line and blocks
+fn parse_go_mod(content: &str, deps: &mut Dependencies) {
+    let mut block: Option<&str> = None;
+
+    for line in content.lines() {
+        let (code, comment) = match line.split_once("//") {
+            Some((code, comment)) => (code.trim(), comment.trim()),
+            None => (line.trim(), ""),
+        };
+        if code.is_empty() {
+            continue;
+        }
+
+        let (directive, args) = match block {
+            Some(_) if code == ")" => {
+                block = None;
+                continue;
+            }
+            Some(directive) => (directive, code),
+            None => {
+                let (directive, args) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+                let args = args.trim();
+                if args == "(" {
+                    block = Some(match directive {
+                        "require" => "require",
+                        "replace" => "replace",
+                        _ => "other",
+                    });
+                    continue;
+                }
+                (directive, args)
+            }
+        };
+
+        match directive {
+            "module" => deps.module = Some(unquote(args).to_string()),
+            "require" => {
+                let mut words = args.split_whitespace().map(unquote);
+                if let (Some(path), Some(version)) = (words.next(), words.next()) {
+                    let module = deps.modules.entry(path.to_string()).or_default();
+                    module.version = version.to_string();
+                    module.direct = !comment.starts_with("indirect");
+                    // Direct requirements are always part of the build
+                    module.built |= module.direct;
+                }
+            }
+            "replace" => {
+                if let Some((old, new)) = args.split_once("=>") {
+                    let old = old.split_whitespace().next().map(unquote).unwrap_or("");
+                    let new = new.split_whitespace().next().map(unquote).unwrap_or("");
+                    if let Some(module) = deps.modules.get_mut(old) {
+                        module.replacement = Some(new.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// go.sum lists every module whose contents were downloaded for the build
+fn parse_go_sum(content: &str, deps: &mut Dependencies) {
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+        let (Some(path), Some(version)) = (words.next(), words.next()) else {
+            continue;
+        };
+        if version.ends_with("/go.mod") {
+            continue;
+        }
+        let module = deps.modules.entry(path.to_string()).or_default();
+        if module.version.is_empty() {
+            module.version = version.to_string();
+        }
+        module.built = true;
+    }
+}
+
+/// vendor/modules.txt lists the modules whose packages are actually compiled
+fn parse_vendor_modules(content: &str, deps: &mut Dependencies) {
+    for module in deps.modules.values_mut() {
+        module.built = false;
+    }
+
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        if let Some(header) = line.strip_prefix("# ") {
+            let mut words = header.split_whitespace();
+            current = words.next().map(String::from);
+            if let (Some(path), Some(version)) = (&current, words.next()) {
+                let module = deps.modules.entry(path.clone()).or_default();
+                if version != "=>" {
+                    module.version = version.to_string();
+                }
+            }
+        } else if line.starts_with("## ") || line.trim().is_empty() {
+            continue;
+        } else if let Some(path) = &current {
+            // Package lines: the module is compiled into the binary
+            if let Some(module) = deps.modules.get_mut(path) {
+                module.built = true;
+            }
+        }
+    }
+}
+
+impl Detector {
+    /// Build the dependency graph of the main module and flag static build risks
+    pub(crate) fn check_dependencies(&self, index: &RepoIndex) -> (Vec<String>, Dependencies) {
+        self.log_verbose("Analyzing module dependencies...");
+        let mut details = Vec::new();
+        let mut deps = Dependencies::default();
+
+        let Some(go_mod) = index.go_mod() else {
+            return (details, deps);
+        };
+        parse_go_mod(go_mod, &mut deps);
+        if let Some(go_sum) = index.text("go.sum") {
+            parse_go_sum(go_sum, &mut deps);
+        }
+        if let Some(modules) = index.text("vendor/modules.txt") {
+            parse_vendor_modules(modules, &mut deps);
+            deps.vendored = true;
+        }
+
+        deps.direct = deps.modules.values().filter(|m| m.direct).count();
+        deps.total = deps.modules.values().filter(|m| m.built).count();
+        details.push(format!(
+            "Dependencies: {} direct, {} total{}",
+            deps.direct,
+            deps.total,
+            if deps.vendored { " (vendored)" } else { "" }
+        ));
+
+        for (path, module) in deps.modules.iter().filter(|(_, m)| m.built) {
+            let candidates = std::iter::once(path.as_str()).chain(module.replacement.as_deref());
+            let risk = candidates.into_iter().find_map(|candidate| {
+                STATIC_BUILD_RISKS.iter().find(|(prefix, _)| {
+                    candidate == *prefix || candidate.starts_with(&format!("{}/", prefix))
+                })
+            });
+            if let Some((_, reason)) = risk {
+                self.log_verbose(&format!("Static build risk: {} ({})", path, reason));
+                details.push(format!("Static build risk: {} ({})", path, reason));
+                deps.risks.push(DependencyRisk {
+                    module: path.clone(),
+                    version: module.version.clone(),
+                    direct: module.direct,
+                    reason,
+                });
+            }
+        }
+
+        (details, deps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GO_MOD: &str = r#"module example.com/tool
+
+go 1.22
+
+require github.com/spf13/cobra v1.8.0
+
+require (
+	github.com/mattn/go-sqlite3 v1.14.22 // indirect
+	golang.org/x/sys v0.20.0 // indirect
+	"github.com/old/sqlite" v1.0.0
+)
+
+replace github.com/old/sqlite => github.com/mattn/go-sqlite3 v1.14.22
+"#;
+
+    fn module<'a>(deps: &'a Dependencies, path: &str) -> &'a Module {
+        deps.modules.get(path).unwrap()
+    }
+
+    #[test]
+    fn go_mod_requires_blocks_and_single_lines() {
+        let mut deps = Dependencies::default();
+        parse_go_mod(GO_MOD, &mut deps);
+
+        assert_eq!(deps.module.as_deref(), Some("example.com/tool"));
+        assert_eq!(deps.modules.len(), 4);
+        let cobra = module(&deps, "github.com/spf13/cobra");
+        assert_eq!(cobra.version, "v1.8.0");
+        assert!(cobra.direct && cobra.built);
+
+        let sqlite = module(&deps, "github.com/mattn/go-sqlite3");
+        assert_eq!(sqlite.version, "v1.14.22");
+        assert!(!sqlite.direct && !sqlite.built);
+
+        let old = module(&deps, "github.com/old/sqlite");
+        assert!(old.direct);
+        assert_eq!(
+            old.replacement.as_deref(),
+            Some("github.com/mattn/go-sqlite3")
+        );
+        assert!(deps.provides("example.com/tool/internal/x"));
+        assert!(deps.provides("github.com/spf13/cobra/doc"));
+        assert!(!deps.provides("golang.org/x/sys/unix"));
+    }
+
+    #[test]
+    fn go_sum_marks_downloaded_modules_as_built() {
+        let mut deps = Dependencies::default();
+        parse_go_mod(GO_MOD, &mut deps);
+        parse_go_sum(
+            "golang.org/x/sys v0.20.0 h1:abc=\n\
+             golang.org/x/sys v0.20.0/go.mod h1:def=\n\
+             github.com/mattn/go-sqlite3 v1.14.22/go.mod h1:ghi=\n\
+             github.com/inconshreveable/mousetrap v1.1.0 h1:jkl=\n",
+            &mut deps,
+        );
+
+        assert!(module(&deps, "golang.org/x/sys").built);
+        // Only its go.mod was needed for version selection
+        assert!(!module(&deps, "github.com/mattn/go-sqlite3").built);
+        let mousetrap = module(&deps, "github.com/inconshreveable/mousetrap");
+        assert_eq!(mousetrap.version, "v1.1.0");
+        assert!(mousetrap.built && !mousetrap.direct);
+    }
+
+    #[test]
+    fn replacement_by_a_risky_module_is_flagged() {
+        let mut index = RepoIndex::default();
+        index.texts.insert("go.mod".into(), GO_MOD.to_string());
+        index.texts.insert(
+            "go.sum".into(),
+            "github.com/old/sqlite v1.0.0 h1:abc=\n".to_string(),
+        );
+        let detector = Detector::new(crate::Options::default()).unwrap();
+        let (_, deps) = detector.check_dependencies(&index);
+
+        assert_eq!(deps.direct, 2);
+        assert_eq!(deps.total, 2);
+        assert_eq!(
+            deps.risks,
+            [DependencyRisk {
+                module: "github.com/old/sqlite".to_string(),
+                version: "v1.0.0".to_string(),
+                direct: true,
+                reason: "SQLite via cgo",
+            }]
+        );
+    }
+
+    #[test]
+    fn vendor_modules_override_built() {
+        let mut deps = Dependencies::default();
+        parse_go_mod(GO_MOD, &mut deps);
+        parse_go_sum("golang.org/x/sys v0.20.0 h1:abc=\n", &mut deps);
+        parse_vendor_modules(
+            "# github.com/spf13/cobra v1.8.0\n\
+             ## explicit; go 1.15\n\
+             github.com/spf13/cobra\n\
+             # golang.org/x/sys v0.21.0\n\
+             ## explicit; go 1.18\n\
+             # github.com/old/sqlite v1.0.0 => github.com/mattn/go-sqlite3 v1.14.22\n\
+             github.com/old/sqlite\n",
+            &mut deps,
+        );
+
+        assert!(module(&deps, "github.com/spf13/cobra").built);
+        let sys = module(&deps, "golang.org/x/sys");
+        assert_eq!(sys.version, "v0.21.0");
+        assert!(!sys.built);
+        assert!(module(&deps, "github.com/old/sqlite").built);
+        assert!(!module(&deps, "github.com/mattn/go-sqlite3").built);
+    }
+}
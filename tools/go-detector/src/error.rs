@@ -0,0 +1,179 @@
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Resolve,
+    Fetch,
+    Extract,
+    Analyze,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Resolve => "resolve",
+            Stage::Fetch => "fetch",
+            Stage::Extract => "extract",
+            Stage::Analyze => "analyze",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotFound,
+    AuthRequired,
+    RateLimited,
+    Network,
+    Extraction,
+    NotGoProject,
+    Timeout,
+    LimitExceeded,
+    Internal,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::AuthRequired => "auth_required",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Network => "network",
+            ErrorKind::Extraction => "extraction",
+            ErrorKind::NotGoProject => "not_go_project",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::LimitExceeded => "limit_exceeded",
+            ErrorKind::Internal => "internal",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Internal => 3,
+            ErrorKind::Timeout => 6,
+            ErrorKind::NotFound => 10,
+            ErrorKind::AuthRequired => 11,
+            ErrorKind::RateLimited => 12,
+            ErrorKind::Network => 13,
+            ErrorKind::Extraction => 14,
+            ErrorKind::NotGoProject => 15,
+            ErrorKind::LimitExceeded => 16,
+        }
+    }
+
+    pub(crate) fn from_http_status(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            404 | 410 => ErrorKind::NotFound,
+            401 | 403 => ErrorKind::AuthRequired,
+            429 => ErrorKind::RateLimited,
+            _ => ErrorKind::Network,
+        }
+    }
+
+    pub(crate) fn from_git_stderr(stderr: &str) -> Self {
+        let stderr = stderr.to_lowercase();
+        if stderr.contains("rate limit") || stderr.contains("429") {
+            ErrorKind::RateLimited
+        } else if stderr.contains("authentication")
+            || stderr.contains("could not read username")
+            || stderr.contains("terminal prompts disabled")
+            || stderr.contains("permission denied")
+            || stderr.contains("403")
+        {
+            ErrorKind::AuthRequired
+        } else if stderr.contains("not found") || stderr.contains("404") {
+            ErrorKind::NotFound
+        } else if stderr.contains("could not resolve host")
+            || stderr.contains("unable to access")
+            || stderr.contains("connection")
+            || stderr.contains("timed out")
+            || stderr.contains("early eof")
+        {
+            ErrorKind::Network
+        } else {
+            ErrorKind::Internal
+        }
+    }
+
+    /// Classify any error bubbling out of the fetch and analysis stages
+    pub(crate) fn of(error: &anyhow::Error) -> Self {
+        if let Some(e) = error.downcast_ref::<DetectError>() {
+            e.kind
+        } else if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+            if e.is_timeout() {
+                ErrorKind::Timeout
+            } else if let Some(status) = e.status() {
+                ErrorKind::from_http_status(status)
+            } else {
+                ErrorKind::Network
+            }
+        } else {
+            ErrorKind::Internal
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DetectError {
+    pub kind: ErrorKind,
+    pub stage: Option<Stage>,
+    pub message: String,
+}
+
+impl DetectError {
+    pub(crate) fn new(kind: ErrorKind, stage: Stage, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            stage: Some(stage),
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn timeout(stage: Stage, after: Duration) -> Self {
+        Self::new(
+            ErrorKind::Timeout,
+            stage,
+            format!(
+                "Timed out during {} after {}s",
+                stage.as_str(),
+                after.as_secs()
+            ),
+        )
+    }
+}
+
+impl fmt::Display for DetectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for DetectError {}
+
+impl From<anyhow::Error> for DetectError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<DetectError>() {
+            Ok(e) => e,
+            Err(error) => Self {
+                kind: ErrorKind::of(&error),
+                stage: None,
+                message: format!("{}", error),
+            },
+        }
+    }
+}
+
+/// Built-in patterns that fail to compile are a bug, not a property of the repo
+impl From<regex::Error> for DetectError {
+    fn from(error: regex::Error) -> Self {
+        Self {
+            kind: ErrorKind::Internal,
+            stage: None,
+            message: format!("Invalid pattern: {}", error),
+        }
+    }
+}
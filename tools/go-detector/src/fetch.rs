@@ -0,0 +1,431 @@
+use crate::error::{DetectError, ErrorKind, Stage};
+use crate::Detector;
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+impl Detector {
+    pub(crate) async fn download_archive(&self, url: &str, extract_dir: &Path) -> Result<()> {
+        self.log_info(&format!("Downloading archive: {}", url));
+
+        let pb = if !self.quiet {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(ProgressStyle::default_spinner().template("{spinner:.blue} {msg}")?);
+            pb.set_message("Downloading...");
+            Some(pb)
+        } else {
+            None
+        };
+
+        let client = reqwest::Client::builder()
+            .connect_timeout(self.limits.resolve_timeout)
+            .build()?;
+
+        let response = self
+            .with_timeout(Stage::Resolve, self.limits.resolve_timeout, async {
+                Ok(client.get(url).send().await?)
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(DetectError::new(
+                ErrorKind::from_http_status(response.status()),
+                Stage::Resolve,
+                format!("Failed to download: HTTP {}", response.status()),
+            )
+            .into());
+        }
+
+        let max_size = self.limits.max_download_size;
+        if let Some(length) = response.content_length() {
+            if length > max_size {
+                return Err(DetectError::new(
+                    ErrorKind::LimitExceeded,
+                    Stage::Fetch,
+                    format!(
+                        "Archive too large: {} bytes (limit {} bytes)",
+                        length, max_size
+                    ),
+                )
+                .into());
+            }
+        }
+
+        // Stream to disk instead of buffering the whole body in memory
+        let archive_path = extract_dir.join("archive");
+        self.with_timeout(Stage::Fetch, self.limits.fetch_timeout, async {
+            let mut response = response;
+            let mut file = tokio::fs::File::create(&archive_path).await?;
+            let mut downloaded: u64 = 0;
+            while let Some(chunk) = response.chunk().await? {
+                downloaded += chunk.len() as u64;
+                if downloaded > max_size {
+                    return Err(DetectError::new(
+                        ErrorKind::LimitExceeded,
+                        Stage::Fetch,
+                        format!("Archive too large: exceeded limit of {} bytes", max_size),
+                    )
+                    .into());
+                }
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+            Ok(())
+        })
+        .await?;
+
+        if let Some(pb) = &pb {
+            pb.set_message("Extracting...");
+        }
+
+        // Detect archive format from content or extension
+        let extracted = self
+            .with_timeout(Stage::Extract, self.limits.extract_timeout, async {
+                if url.ends_with(".zip") || self.is_zip_file(&archive_path)? {
+                    self.try_extract_zip(&archive_path, extract_dir).await
+                } else {
+                    self.try_extract_tar(&archive_path, extract_dir).await
+                }
+            })
+            .await;
+
+        fs::remove_file(&archive_path).ok();
+
+        if let Some(pb) = pb {
+            pb.finish_with_message("Done");
+        }
+
+        extracted
+    }
+
+    pub(crate) fn is_zip_file(&self, path: &Path) -> Result<bool> {
+        let mut magic = [0u8; 4];
+        let mut file = fs::File::open(path)?;
+        Ok(std::io::Read::read_exact(&mut file, &mut magic).is_ok() && &magic == b"PK\x03\x04")
+    }
+
+    /// Count the entries an archive listing prints, one per line, failing
+    /// as soon as there are more than `max_files` so that nothing is
+    /// unpacked. `None` when the listing command fails.
+    async fn count_entries(&self, program: &str, args: &[&str]) -> Result<Option<usize>> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let mut entries = BufReader::new(child.stdout.take().unwrap()).split(b'\n');
+        let mut count = 0;
+        while entries.next_segment().await?.is_some() {
+            count += 1;
+            if count > self.limits.max_files {
+                child.kill().await.ok();
+                return Err(DetectError::new(
+                    ErrorKind::LimitExceeded,
+                    Stage::Extract,
+                    format!(
+                        "Too many files in archive: exceeded limit of {}",
+                        self.limits.max_files
+                    ),
+                )
+                .into());
+            }
+        }
+        Ok(child.wait().await?.success().then_some(count))
+    }
+
+    fn extraction_failed() -> anyhow::Error {
+        DetectError::new(
+            ErrorKind::Extraction,
+            Stage::Extract,
+            "Failed to extract archive",
+        )
+        .into()
+    }
+
+    pub(crate) async fn try_extract_tar(
+        &self,
+        archive_path: &Path,
+        extract_dir: &Path,
+    ) -> Result<()> {
+        let archive = archive_path.to_str().unwrap();
+
+        // Gzipped first, then whatever tar detects on its own
+        for (list, extract) in [("-tzf", "-xzf"), ("-tf", "-xf")] {
+            if self.count_entries("tar", &[list, archive]).await?.is_none() {
+                continue;
+            }
+            let status = Command::new("tar")
+                .args([extract, archive, "--strip-components=1"])
+                .current_dir(extract_dir)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .kill_on_drop(true)
+                .status()
+                .await?;
+            return if status.success() {
+                Ok(())
+            } else {
+                Err(Self::extraction_failed())
+            };
+        }
+        Err(Self::extraction_failed())
+    }
+
+    pub(crate) async fn try_extract_zip(
+        &self,
+        archive_path: &Path,
+        extract_dir: &Path,
+    ) -> Result<()> {
+        let archive = archive_path.to_str().unwrap();
+        if self
+            .count_entries("unzip", &["-Z1", archive])
+            .await?
+            .is_none()
+        {
+            return Err(Self::extraction_failed());
+        }
+
+        let output = Command::new("unzip")
+            .args(["-q", "-o", archive, "-d", extract_dir.to_str().unwrap()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .status()
+            .await?;
+
+        if output.success() {
+            self.handle_single_directory_extraction(extract_dir)?;
+            Ok(())
+        } else {
+            Err(Self::extraction_failed())
+        }
+    }
+
+    pub(crate) fn handle_single_directory_extraction(&self, extract_dir: &Path) -> Result<()> {
+        let entries: Vec<_> = fs::read_dir(extract_dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .collect();
+
+        if entries.len() == 1 {
+            let source = entries[0].path();
+            self.move_directory_contents(&source, extract_dir)?;
+            fs::remove_dir_all(&source).ok();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn move_directory_contents(&self, source: &Path, dest: &Path) -> Result<()> {
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            let dest_path = dest.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                fs::rename(entry.path(), dest_path)?;
+            } else {
+                fs::copy(entry.path(), dest_path)?;
+                fs::remove_file(entry.path()).ok();
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up the default branch with `git ls-remote` within `resolve_timeout`
+    async fn resolve_git(&self, url: &str) -> Result<()> {
+        let output = self
+            .with_timeout(Stage::Resolve, self.limits.resolve_timeout, async {
+                Ok(Command::new("git")
+                    .args(["ls-remote", "--quiet", url, "HEAD"])
+                    .env("GIT_TERMINAL_PROMPT", "0")
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .output()
+                    .await?)
+            })
+            .await?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(DetectError::new(
+            ErrorKind::from_git_stderr(&stderr),
+            Stage::Resolve,
+            format!("Git remote lookup failed: {}", stderr.trim()),
+        )
+        .into())
+    }
+
+    pub(crate) async fn clone_git(&self, url: &str, clone_dir: &Path) -> Result<()> {
+        self.log_info(&format!("Cloning repository: {}", url));
+
+        // Reaching the remote is the resolve stage, like the HTTP request of
+        // an archive download, so an unreachable host fails fast
+        self.resolve_git(url).await?;
+
+        let pb = if !self.quiet {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(ProgressStyle::default_spinner().template("{spinner:.blue} {msg}")?);
+            pb.set_message("Cloning...");
+            Some(pb)
+        } else {
+            None
+        };
+
+        // Use shallow clone for better performance
+        let output = self
+            .with_timeout(Stage::Fetch, self.limits.fetch_timeout, async {
+                Ok(Command::new("git")
+                    .args([
+                        "clone",
+                        "--depth=1",
+                        "--filter=blob:none",
+                        "--quiet",
+                        url,
+                        clone_dir.to_str().unwrap(),
+                    ])
+                    // Never block on a credential prompt for private repos
+                    .env("GIT_TERMINAL_PROMPT", "0")
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .output()
+                    .await?)
+            })
+            .await;
+
+        if let Some(pb) = pb {
+            pb.finish_with_message("Done");
+        }
+
+        let output = output?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(DetectError::new(
+                ErrorKind::from_git_stderr(&stderr),
+                Stage::Fetch,
+                format!("Git clone failed: {}", stderr.trim()),
+            )
+            .into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Limits, Options};
+
+    fn detector(max_files: usize) -> Detector {
+        Detector::new(Options {
+            quiet: true,
+            verbose: false,
+            limits: Limits {
+                max_files,
+                ..Limits::default()
+            },
+        })
+        .unwrap()
+    }
+
+    /// A `repo/` directory holding `files` files, packed with `command`
+    fn archive(dir: &Path, files: usize, command: &[&str]) {
+        let repo = dir.join("repo");
+        fs::create_dir(&repo).unwrap();
+        for i in 0..files {
+            fs::write(repo.join(format!("{}.go", i)), "package main\n").unwrap();
+        }
+        let status = std::process::Command::new(command[0])
+            .args(&command[1..])
+            .arg("repo")
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn kind(result: Result<()>) -> Option<ErrorKind> {
+        result.err().map(|e| ErrorKind::of(&e))
+    }
+
+    #[tokio::test]
+    async fn archives_over_the_file_limit_are_not_unpacked() {
+        for command in [
+            ["tar", "-czf", "archive.tar.gz"],
+            ["zip", "-qr", "archive.zip"],
+        ] {
+            let tmp = tempfile::tempdir().unwrap();
+            archive(tmp.path(), 5, &command);
+            let path = tmp.path().join(command[2]);
+            let out = tmp.path().join("out");
+            fs::create_dir(&out).unwrap();
+
+            let extract = |detector: Detector| {
+                let (path, out) = (path.clone(), out.clone());
+                async move {
+                    if command[0] == "zip" {
+                        detector.try_extract_zip(&path, &out).await
+                    } else {
+                        detector.try_extract_tar(&path, &out).await
+                    }
+                }
+            };
+
+            assert_eq!(
+                kind(extract(detector(3)).await),
+                Some(ErrorKind::LimitExceeded)
+            );
+            assert_eq!(fs::read_dir(&out).unwrap().count(), 0);
+
+            assert_eq!(kind(extract(detector(10)).await), None);
+            assert_eq!(fs::read_dir(&out).unwrap().count(), 5);
+        }
+    }
+
+    #[tokio::test]
+    async fn unreadable_archives_fail_extraction() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("archive");
+        fs::write(&path, "not an archive").unwrap();
+        let detector = detector(10);
+        assert_eq!(
+            kind(detector.try_extract_tar(&path, tmp.path()).await),
+            Some(ErrorKind::Extraction)
+        );
+        assert_eq!(
+            kind(detector.try_extract_zip(&path, tmp.path()).await),
+            Some(ErrorKind::Extraction)
+        );
+    }
+
+    #[tokio::test]
+    async fn git_remotes_are_resolved_before_cloning() {
+        let tmp = tempfile::tempdir().unwrap();
+        let status = std::process::Command::new("git")
+            .args(["init", "--quiet"])
+            .current_dir(tmp.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let detector = detector(10);
+        assert!(detector
+            .resolve_git(tmp.path().to_str().unwrap())
+            .await
+            .is_ok());
+
+        let missing = tmp.path().join("missing");
+        let error = detector
+            .resolve_git(missing.to_str().unwrap())
+            .await
+            .unwrap_err();
+        let error = DetectError::from(error);
+        assert!(matches!(error.stage, Some(Stage::Resolve)), "{:?}", error);
+    }
+}
@@ -0,0 +1,439 @@
+use crate::index::{is_build_file, RepoIndex};
+use crate::Detector;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A `go build` invocation found in a Dockerfile, Makefile or CI workflow
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BuildHint {
+    /// File the command was found in, relative to the repository root
+    pub file: String,
+    pub command: String,
+    pub output: Option<String>,
+    pub packages: Vec<String>,
+    pub tags: Vec<String>,
+    pub ldflags: Option<String>,
+    /// Inline `KEY=value` assignments such as `CGO_ENABLED=0`
+    pub env: Vec<String>,
+}
+
+/// `go build` flags that consume the following argument
+pub(crate) const VALUE_FLAGS: &[&str] = &[
+    "-o",
+    "-tags",
+    "-ldflags",
+    "-gcflags",
+    "-asmflags",
+    "-gccgoflags",
+    "-buildmode",
+    "-mod",
+    "-modfile",
+    "-p",
+    "-pkgdir",
+    "-overlay",
+    "-pgo",
+    "-compiler",
+    "-installsuffix",
+    "-toolexec",
+    "-C",
+];
+
+/// Split a shell command into words, honouring quotes and backslash escapes
+pub(crate) fn shell_words(input: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    current.push(c);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            if let Some(next) = chars.next() {
+                                current.push(next);
+                            }
+                        }
+                        _ => current.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            _ => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+/// Split a shell line into commands on `&&`, `||`, `;` and `|` outside quotes
+pub(crate) fn split_commands(line: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => {
+                quote = None;
+                current.push(c);
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (None, ';') => commands.push(std::mem::take(&mut current)),
+            (None, '&') if chars.peek() == Some(&'&') => {
+                chars.next();
+                commands.push(std::mem::take(&mut current));
+            }
+            (None, '|') => {
+                if chars.peek() == Some(&'|') {
+                    chars.next();
+                }
+                commands.push(std::mem::take(&mut current));
+            }
+            (None, c) => current.push(c),
+        }
+    }
+    commands.push(current);
+    commands
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// Parse a single command; returns `None` unless it is a `go build`
+pub(crate) fn parse_go_build(command: &str, file: &str) -> Option<BuildHint> {
+    let words = shell_words(command);
+    let mut env = Vec::new();
+    let mut rest = words.iter().map(String::as_str).peekable();
+
+    // Leading `env` and `KEY=value` prefixes
+    while let Some(word) = rest.peek() {
+        if *word == "env" {
+            rest.next();
+        } else if word.contains('=') && !word.starts_with('-') {
+            env.push(word.to_string());
+            rest.next();
+        } else {
+            break;
+        }
+    }
+
+    let go = rest.next()?;
+    if !(go == "go" || go.ends_with("/go")) || rest.next()? != "build" {
+        return None;
+    }
+
+    let mut hint = BuildHint {
+        file: file.to_string(),
+        command: command.trim().to_string(),
+        env,
+        ..Default::default()
+    };
+
+    while let Some(word) = rest.next() {
+        let (flag, inline) = match word.split_once('=') {
+            Some((flag, value)) if word.starts_with('-') => (flag, Some(value.to_string())),
+            _ => (word, None),
+        };
+        // Both `-flag` and `--flag` are accepted by the go tool
+        let flag = if flag.starts_with("--") {
+            &flag[1..]
+        } else {
+            flag
+        };
+
+        if !flag.starts_with('-') {
+            hint.packages.push(word.to_string());
+            continue;
+        }
+        if !VALUE_FLAGS.contains(&flag) {
+            continue;
+        }
+        let Some(value) = inline.or_else(|| rest.next().map(String::from)) else {
+            break;
+        };
+        match flag {
+            "-o" => hint.output = Some(value),
+            "-ldflags" => hint.ldflags = Some(value),
+            "-tags" => hint.tags.extend(
+                value
+                    .split([',', ' '])
+                    .filter(|t| !t.is_empty())
+                    .map(String::from),
+            ),
+            _ => {}
+        }
+    }
+
+    if hint.packages.is_empty() {
+        hint.packages.push(".".to_string());
+    }
+    Some(hint)
+}
+
+/// Join lines ending with a backslash continuation
+pub(crate) fn join_continuations(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        match line.trim_end().strip_suffix('\\') {
+            Some(head) => {
+                current.push_str(head);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+pub(crate) fn parse_dockerfile(content: &str, file: &str) -> Vec<BuildHint> {
+    join_continuations(content)
+        .iter()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (instruction, args) = line.split_once(char::is_whitespace)?;
+            instruction.eq_ignore_ascii_case("RUN").then_some(args)
+        })
+        .flat_map(split_commands)
+        .filter_map(|command| parse_go_build(&command, file))
+        .collect()
+}
+
+/// Split a `VAR op value` line on its first `=`, so that `FOO:=a=b` assigns
+/// `a=b` to `FOO`; returns the name, operator and value
+pub(crate) fn parse_assignment(line: &str) -> Option<(&str, &str, &str)> {
+    let eq = line.find('=')?;
+    let head = &line[..eq];
+    let (name, op) = ["::", ":", "?", "+"]
+        .iter()
+        .find_map(|prefix| head.strip_suffix(prefix).map(|name| (name, prefix.len())))
+        .map_or((head, "="), |(name, len)| (name, &line[eq - len..=eq]));
+    let name = name.trim().trim_start_matches("export ").trim();
+    // `!=` runs a shell command, its value is unknown
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '!') {
+        return None;
+    }
+    Some((name, op, line[eq + 1..].trim()))
+}
+
+pub(crate) fn parse_makefile(content: &str, file: &str) -> Vec<BuildHint> {
+    let lines = join_continuations(content);
+
+    // Simple `VAR = value` style assignments, enough for `-o $(BINARY)`
+    let mut vars: HashMap<String, String> = HashMap::new();
+    for line in &lines {
+        if line.starts_with('\t') || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let Some((name, op, value)) = parse_assignment(line) else {
+            continue;
+        };
+        match vars.get_mut(name) {
+            Some(existing) if op == "+=" => {
+                existing.push(' ');
+                existing.push_str(value);
+            }
+            Some(_) => {}
+            None => {
+                vars.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+
+    let expand = |text: &str| {
+        let mut text = text.to_string();
+        for (name, value) in &vars {
+            text = text
+                .replace(&format!("$({})", name), value)
+                .replace(&format!("${{{}}}", name), value);
+        }
+        text
+    };
+
+    lines
+        .iter()
+        .filter_map(|line| line.strip_prefix('\t'))
+        // Strip make's echo/ignore-error prefixes
+        .map(|recipe| expand(recipe.trim_start_matches(['@', '-', '+'])))
+        .flat_map(|recipe| split_commands(&recipe))
+        .filter_map(|command| parse_go_build(&command, file))
+        .collect()
+}
+
+impl Detector {
+    pub(crate) fn check_build_hints(
+        &self,
+        index: &RepoIndex,
+    ) -> (i32, Vec<String>, Vec<BuildHint>) {
+        self.log_verbose("Parsing Dockerfiles and Makefiles for build commands...");
+        let mut hints = Vec::new();
+        let mut details = Vec::new();
+
+        for (path, content) in index.texts_sorted() {
+            let is_build_file = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(is_build_file);
+            if !is_build_file {
+                continue;
+            }
+            let relative = path.to_string_lossy().to_string();
+            let name = relative.to_lowercase();
+            let found = if name.contains("makefile") || name.ends_with(".mk") {
+                parse_makefile(content, &relative)
+            } else {
+                parse_dockerfile(content, &relative)
+            };
+            for hint in &found {
+                self.log_verbose(&format!(
+                    "Found build command in {}: {}",
+                    relative, hint.command
+                ));
+                details.push(format!(
+                    "Build hint: {} -> {}",
+                    relative,
+                    hint.output.as_deref().unwrap_or(&hint.packages.join(" "))
+                ));
+            }
+            hints.extend(found);
+        }
+
+        let score = if hints.is_empty() { 0 } else { 2 };
+        if score > 0 {
+            self.log_verbose("Found explicit go build commands (+2 points)");
+        }
+        (score, details, hints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_words_honour_quotes_and_escapes() {
+        assert_eq!(
+            shell_words(r#"go build -ldflags "-s -w -X 'main.v=1'" ./cmd/a\ b 'x y'"#),
+            [
+                "go",
+                "build",
+                "-ldflags",
+                "-s -w -X 'main.v=1'",
+                "./cmd/a b",
+                "x y"
+            ]
+        );
+        assert_eq!(
+            shell_words(r#"echo "a \"b\"" ''"#),
+            ["echo", r#"a "b""#, ""]
+        );
+    }
+
+    #[test]
+    fn commands_split_outside_quotes() {
+        assert_eq!(
+            split_commands("cd src && go build -o app . ; echo 'a && b' || true | cat"),
+            [
+                "cd src",
+                "go build -o app .",
+                "echo 'a && b'",
+                "true",
+                "cat"
+            ]
+        );
+    }
+
+    #[test]
+    fn go_build_flags_are_extracted() {
+        let hint = parse_go_build(
+            r#"CGO_ENABLED=0 env GOOS=linux go build -o=bin/app -tags "netgo,osusergo" --ldflags '-s -w' -trimpath ./cmd/app"#,
+            "Dockerfile",
+        )
+        .unwrap();
+        assert_eq!(hint.env, ["CGO_ENABLED=0", "GOOS=linux"]);
+        assert_eq!(hint.output.as_deref(), Some("bin/app"));
+        assert_eq!(hint.tags, ["netgo", "osusergo"]);
+        assert_eq!(hint.ldflags.as_deref(), Some("-s -w"));
+        assert_eq!(hint.packages, ["./cmd/app"]);
+
+        assert_eq!(
+            parse_go_build("go build", "Makefile").unwrap().packages,
+            ["."]
+        );
+        assert!(parse_go_build("go test ./...", "Makefile").is_none());
+        assert!(parse_go_build("echo go build", "Makefile").is_none());
+    }
+
+    #[test]
+    fn dockerfile_continuations_are_joined() {
+        let hints = parse_dockerfile(
+            "FROM golang\nRUN apk add git && \\\n    go build \\\n      -o /app ./cmd/app\n",
+            "Dockerfile",
+        );
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].output.as_deref(), Some("/app"));
+        assert_eq!(hints[0].packages, ["./cmd/app"]);
+    }
+
+    #[test]
+    fn assignments_split_on_the_first_operator() {
+        assert_eq!(parse_assignment("FOO:=a=b"), Some(("FOO", ":=", "a=b")));
+        assert_eq!(parse_assignment("X ?= y"), Some(("X", "?=", "y")));
+        assert_eq!(parse_assignment("X ::= y:=z"), Some(("X", "::=", "y:=z")));
+        assert_eq!(parse_assignment("X += -s"), Some(("X", "+=", "-s")));
+        assert_eq!(parse_assignment("export X = y"), Some(("X", "=", "y")));
+        assert_eq!(parse_assignment("X != git describe"), None);
+        assert_eq!(parse_assignment("build: FOO=1"), None);
+    }
+
+    #[test]
+    fn makefile_variables_are_expanded() {
+        let makefile = "BINARY ?= app\nLDFLAGS := -X main.v=1\nLDFLAGS += -s\n\
+            BINARY = other\n\nbuild:\n\t@go build -o $(BINARY) \\\n\t\t-ldflags \"${LDFLAGS}\" .\n";
+        let hints = parse_makefile(makefile, "Makefile");
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].output.as_deref(), Some("app"));
+        assert_eq!(hints[0].ldflags.as_deref(), Some("-X main.v=1 -s"));
+    }
+}
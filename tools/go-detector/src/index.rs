@@ -0,0 +1,306 @@
+use crate::error::{DetectError, ErrorKind, Stage};
+use crate::Detector;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Text files larger than this are not kept in the index, except go.sum
+const MAX_TEXT_SIZE: u64 = 1024 * 1024;
+
+const README_NAMES: &[&str] = &[
+    "README.md",
+    "readme.md",
+    "README",
+    "readme",
+    "README.txt",
+    "readme.txt",
+];
+
+#[derive(Debug)]
+pub(crate) struct GoFileInfo {
+    /// Path relative to the repository root
+    pub(crate) path: PathBuf,
+    pub(crate) package_name: String,
+    pub(crate) has_main_func: bool,
+    pub(crate) imports: Vec<String>,
+    pub(crate) has_cli_patterns: bool,
+}
+
+/// Everything the scoring rules need, gathered in one walk of the tree
+#[derive(Debug, Default)]
+pub(crate) struct RepoIndex {
+    pub(crate) dirs: HashSet<PathBuf>,
+    pub(crate) files: HashSet<PathBuf>,
+    /// Parsed headers of non-test `.go` files outside ignored directories
+    pub(crate) go_files: Vec<GoFileInfo>,
+    /// Contents of READMEs, go.mod, build and CI files
+    pub(crate) texts: HashMap<PathBuf, String>,
+}
+
+impl RepoIndex {
+    fn merge(&mut self, other: RepoIndex) {
+        self.dirs.extend(other.dirs);
+        self.files.extend(other.files);
+        self.go_files.extend(other.go_files);
+        self.texts.extend(other.texts);
+    }
+
+    pub(crate) fn has_dir(&self, path: &str) -> bool {
+        self.dirs.contains(Path::new(path))
+    }
+
+    pub(crate) fn has_file(&self, path: &str) -> bool {
+        self.files.contains(Path::new(path))
+    }
+
+    pub(crate) fn text(&self, path: &str) -> Option<&str> {
+        self.texts.get(Path::new(path)).map(String::as_str)
+    }
+
+    pub(crate) fn go_mod(&self) -> Option<&str> {
+        self.text("go.mod")
+    }
+
+    pub(crate) fn readme(&self) -> Option<&str> {
+        README_NAMES.iter().find_map(|name| self.text(name))
+    }
+
+    /// Names of files and directories directly under the root
+    pub(crate) fn top_level(&self) -> impl Iterator<Item = &Path> {
+        self.dirs
+            .iter()
+            .chain(self.files.iter())
+            .filter(|p| p.parent() == Some(Path::new("")))
+            .map(PathBuf::as_path)
+    }
+
+    /// Indexed texts in path order
+    pub(crate) fn texts_sorted(&self) -> Vec<(&Path, &str)> {
+        let mut texts: Vec<_> = self
+            .texts
+            .iter()
+            .map(|(path, text)| (path.as_path(), text.as_str()))
+            .collect();
+        texts.sort();
+        texts
+    }
+}
+
+enum DirPolicy {
+    Skip,
+    /// Only `modules.txt` is read, vendored sources are not analyzed
+    Vendor,
+    /// Walked for special files only
+    NoGo,
+    Normal,
+}
+
+fn dir_policy(name: &str) -> DirPolicy {
+    match name {
+        "node_modules" | ".git" | ".gitignore" | "testdata" | "tests" | "_test" | "docs"
+        | "documentation" | "examples" => DirPolicy::Skip,
+        "vendor" => DirPolicy::Vendor,
+        ".github" => DirPolicy::NoGo,
+        _ => DirPolicy::Normal,
+    }
+}
+
+pub(crate) fn is_build_file(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("dockerfile")
+        || name.ends_with(".dockerfile")
+        || name.starts_with("containerfile")
+        || name == "makefile"
+        || name == "gnumakefile"
+        || name.ends_with(".mk")
+}
+
+/// Whether the contents of a file should be kept in the index
+fn is_indexed_text(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let parent = path.parent().and_then(|p| p.to_str()).unwrap_or("");
+    let lower = name.to_lowercase();
+
+    match parent {
+        "" => {
+            README_NAMES.contains(&name)
+                || name == "go.mod"
+                || name == "go.sum"
+                || is_build_file(name)
+                || lower.starts_with("goreleaser.y")
+                || lower.starts_with(".goreleaser.y")
+        }
+        "build" | "docker" => is_build_file(name),
+        _ => path.starts_with(".github") && (lower.ends_with(".yml") || lower.ends_with(".yaml")),
+    }
+}
+
+pub(crate) fn is_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| name.ends_with("_test.go"))
+}
+
+pub(crate) fn is_example_file(path: &Path) -> bool {
+    let path_str = format!("/{}", path.to_string_lossy().to_lowercase());
+    path_str.contains("/example")
+        || path_str.contains("/demo")
+        || path_str.contains("/sample")
+        || path_str.contains("_example.go")
+}
+
+impl Detector {
+    fn analyze_go_file(&self, path: &Path, relative: &Path) -> Result<GoFileInfo> {
+        let content = fs::read_to_string(path)?;
+        let lines: Vec<&str> = content.lines().collect();
+
+        let mut package_name = String::new();
+        let mut imports = Vec::new();
+        let mut has_main_func = false;
+        let has_cli_patterns = self.cli_patterns.is_match(&content);
+
+        for line in &lines {
+            if package_name.is_empty() && self.main_package_regex.is_match(line) {
+                package_name = "main".to_string();
+            } else if package_name.is_empty() && line.trim().starts_with("package ") {
+                package_name = line
+                    .trim()
+                    .strip_prefix("package ")
+                    .unwrap_or("")
+                    .trim()
+                    .to_string();
+            }
+
+            if line.contains("func main(") {
+                has_main_func = true;
+            }
+
+            // Extract imports
+            if let Some(caps) = self.import_regex.captures(line) {
+                for i in 1..=4 {
+                    if let Some(import) = caps.get(i) {
+                        imports.push(import.as_str().to_string());
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(GoFileInfo {
+            path: relative.to_path_buf(),
+            package_name,
+            has_main_func,
+            imports,
+            has_cli_patterns,
+        })
+    }
+
+    /// Walk the repository once, in parallel, parsing Go files as they are found
+    pub(crate) fn build_index(&self, repo_dir: &Path) -> Result<RepoIndex> {
+        self.log_verbose("Indexing repository...");
+        let seen = AtomicUsize::new(0);
+        let mut index = self.index_dir(repo_dir, Path::new(""), true, 0, &seen)?;
+        index.go_files.sort_by(|a, b| a.path.cmp(&b.path));
+        self.log_verbose(&format!(
+            "Indexed {} files, {} Go files",
+            index.files.len(),
+            index.go_files.len()
+        ));
+        Ok(index)
+    }
+
+    fn index_dir(
+        &self,
+        root: &Path,
+        relative: &Path,
+        go_allowed: bool,
+        depth: usize,
+        seen: &AtomicUsize,
+    ) -> Result<RepoIndex> {
+        self.check_cancelled()?;
+
+        // Limit recursion depth to avoid issues
+        if depth > 10 {
+            return Ok(RepoIndex::default());
+        }
+
+        let entries = fs::read_dir(root.join(relative))?.collect::<Result<Vec<_>, _>>()?;
+        if seen.fetch_add(entries.len(), Ordering::Relaxed) + entries.len() > self.limits.max_files
+        {
+            return Err(DetectError::new(
+                ErrorKind::LimitExceeded,
+                Stage::Analyze,
+                format!(
+                    "Too many files: exceeded limit of {}",
+                    self.limits.max_files
+                ),
+            )
+            .into());
+        }
+
+        entries
+            .par_iter()
+            .map(|entry| {
+                let name = entry.file_name();
+                let path = relative.join(&name);
+                let file_type = entry.file_type()?;
+                let mut index = RepoIndex::default();
+
+                // Symlinks are not followed to avoid cycles
+                if file_type.is_dir() {
+                    let go_allowed = match dir_policy(&name.to_string_lossy()) {
+                        DirPolicy::Skip => return Ok(index),
+                        DirPolicy::Vendor => {
+                            let modules = path.join("modules.txt");
+                            if let Ok(content) = fs::read_to_string(root.join(&modules)) {
+                                index.texts.insert(modules.clone(), content);
+                                index.files.insert(modules);
+                            }
+                            index.dirs.insert(path);
+                            return Ok(index);
+                        }
+                        DirPolicy::NoGo => false,
+                        DirPolicy::Normal => go_allowed,
+                    };
+                    index.merge(self.index_dir(root, &path, go_allowed, depth + 1, seen)?);
+                    index.dirs.insert(path);
+                } else if file_type.is_file() {
+                    let absolute = entry.path();
+                    if go_allowed
+                        && path.extension().is_some_and(|ext| ext == "go")
+                        && !is_test_file(&path)
+                    {
+                        if let Ok(info) = self.analyze_go_file(&absolute, &path) {
+                            index.go_files.push(info);
+                        }
+                    } else if is_indexed_text(&path) {
+                        let size = entry.metadata().map_or(0, |m| m.len());
+                        // go.sum is often larger but needed whole for the dependency graph
+                        if size <= MAX_TEXT_SIZE || path == Path::new("go.sum") {
+                            if let Ok(content) = fs::read_to_string(&absolute) {
+                                index.texts.insert(path.clone(), content);
+                            }
+                        } else {
+                            self.log_info(&format!(
+                                "Skipping {}: {} bytes exceeds the {} byte text limit",
+                                path.display(),
+                                size,
+                                MAX_TEXT_SIZE
+                            ));
+                        }
+                    }
+                    index.files.insert(path);
+                }
+                Ok(index)
+            })
+            .try_reduce(RepoIndex::default, |mut a, b| {
+                a.merge(b);
+                Ok(a)
+            })
+    }
+}
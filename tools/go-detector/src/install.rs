@@ -0,0 +1,313 @@
+use crate::hints::{shell_words, split_commands, VALUE_FLAGS};
+use crate::index::RepoIndex;
+use crate::Detector;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A `go install <package>@<version>` command documented in the README
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstallTarget {
+    pub package: String,
+    pub version: String,
+    pub command: String,
+    /// Main package directories in this repository the target resolves to,
+    /// empty when it points outside the module or at a non-main package
+    pub dirs: Vec<String>,
+}
+
+/// Lines inside fenced and indented code blocks plus inline code spans from
+/// the rest
+fn readme_code(content: &str) -> Vec<String> {
+    let mut code = Vec::new();
+    let mut fence: Option<&str> = None;
+    // An indented code block cannot interrupt a paragraph
+    let mut in_paragraph = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            } else {
+                code.push(line.to_string());
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            fence = Some("```");
+            in_paragraph = false;
+        } else if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+            in_paragraph = false;
+        } else if !in_paragraph
+            && !trimmed.is_empty()
+            && (line.starts_with("    ") || line.starts_with('\t'))
+        {
+            code.push(trimmed.to_string());
+        } else {
+            in_paragraph = !trimmed.is_empty();
+            code.extend(line.split('`').skip(1).step_by(2).map(String::from));
+        }
+    }
+    code
+}
+
+/// Extract `(package, version, command)` for every versioned `go install`
+fn parse_go_install(line: &str) -> Vec<(String, String, String)> {
+    // Shell prompts as commonly pasted into READMEs
+    let line = line.trim();
+    let line = line
+        .strip_prefix("$ ")
+        .or_else(|| line.strip_prefix("> "))
+        .or_else(|| line.strip_prefix("# "))
+        .unwrap_or(line);
+
+    let mut targets = Vec::new();
+    for command in split_commands(line) {
+        let words = shell_words(&command);
+        let mut rest = words
+            .iter()
+            .map(String::as_str)
+            .skip_while(|w| *w == "env" || (w.contains('=') && !w.starts_with('-')));
+
+        let Some(go) = rest.next() else {
+            continue;
+        };
+        if !(go == "go" || go.ends_with("/go")) || rest.next() != Some("install") {
+            continue;
+        }
+
+        while let Some(word) = rest.next() {
+            if word.starts_with('-') {
+                if !word.contains('=') && VALUE_FLAGS.contains(&word.trim_start_matches('-')) {
+                    rest.next();
+                }
+                continue;
+            }
+            if let Some((package, version)) = word.rsplit_once('@') {
+                if !package.is_empty() && !version.is_empty() {
+                    targets.push((package.to_string(), version.to_string(), command.clone()));
+                }
+            }
+        }
+    }
+    targets
+}
+
+/// Path of the `module` directive, which must be followed by whitespace
+fn module_path(go_mod: &str) -> Option<&str> {
+    go_mod.lines().find_map(|line| {
+        let code = line.split_once("//").map_or(line, |(code, _)| code);
+        let (directive, path) = code.trim().split_once(char::is_whitespace)?;
+        let path = path.trim().trim_matches('"').trim_matches('`');
+        (directive == "module" && !path.is_empty()).then_some(path)
+    })
+}
+
+impl Detector {
+    /// Cross-check README install commands against the discovered main packages
+    pub(crate) fn check_install_targets(
+        &self,
+        index: &RepoIndex,
+        main_dirs: &[PathBuf],
+    ) -> (Vec<String>, Vec<InstallTarget>) {
+        self.log_verbose("Extracting go install targets from README...");
+        let mut details = Vec::new();
+        let mut targets: Vec<InstallTarget> = Vec::new();
+
+        let Some(readme) = index.readme() else {
+            return (details, targets);
+        };
+        let module = index.go_mod().and_then(module_path);
+
+        for line in readme_code(readme) {
+            for (package, version, command) in parse_go_install(&line) {
+                if targets.iter().any(|t| t.package == package) {
+                    continue;
+                }
+
+                let (package_path, recursive) = match package.strip_suffix("/...") {
+                    Some(prefix) => (prefix, true),
+                    None => (package.as_str(), false),
+                };
+                let relative = module.and_then(|module| {
+                    if package_path == module {
+                        Some("")
+                    } else {
+                        package_path
+                            .strip_prefix(module)
+                            .and_then(|p| p.strip_prefix('/'))
+                    }
+                });
+
+                let mut dirs: Vec<String> = match relative {
+                    Some(relative) => main_dirs
+                        .iter()
+                        .filter(|dir| {
+                            if recursive {
+                                dir.starts_with(relative)
+                            } else {
+                                dir.as_path() == Path::new(relative)
+                            }
+                        })
+                        .map(|dir| match dir.to_string_lossy().as_ref() {
+                            "" => "./".to_string(),
+                            dir => format!("./{}", dir),
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                };
+                dirs.sort();
+                dirs.dedup();
+
+                if dirs.is_empty() {
+                    self.log_verbose(&format!(
+                        "README installs {}@{} but no matching main package was found",
+                        package, version
+                    ));
+                    details.push(format!("Install target: {} (unverified)", package));
+                } else {
+                    self.log_verbose(&format!(
+                        "README installs {}@{} -> {}",
+                        package,
+                        version,
+                        dirs.join(", ")
+                    ));
+                    details.push(format!(
+                        "Install target: {} -> {}",
+                        package,
+                        dirs.join(", ")
+                    ));
+                }
+
+                targets.push(InstallTarget {
+                    package,
+                    version,
+                    command,
+                    dirs,
+                });
+            }
+        }
+
+        (details, targets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_directive_needs_whitespace() {
+        assert_eq!(
+            module_path("modulefoo x\nmodule example.com/tool // main\n"),
+            Some("example.com/tool")
+        );
+        assert_eq!(
+            module_path("module \"example.com/q\"\n"),
+            Some("example.com/q")
+        );
+        assert_eq!(module_path("module\n"), None);
+    }
+
+    #[test]
+    fn code_comes_from_fences_indented_blocks_and_spans() {
+        let readme = "# Tool\n\
+            Install with `go install example.com/a@latest` or:\n\
+            \n\
+            ```sh\n\
+            $ go install example.com/b@v1.0.0\n\
+            ```\n\
+            \n\
+            \x20   go install example.com/c@latest\n\
+            \tgo install example.com/d@latest\n\
+            \n\
+            A paragraph\n\
+            \x20   continued, not code\n\
+            ~~~\n\
+            go install example.com/e@latest\n\
+            ~~~\n";
+        assert_eq!(
+            readme_code(readme),
+            [
+                "go install example.com/a@latest",
+                "$ go install example.com/b@v1.0.0",
+                "go install example.com/c@latest",
+                "go install example.com/d@latest",
+                "go install example.com/e@latest",
+            ]
+        );
+    }
+
+    #[test]
+    fn several_targets_on_one_line() {
+        let targets = parse_go_install(
+            "$ CGO_ENABLED=0 go install -ldflags '-s -w' example.com/x/cmd/a@latest example.com/x/cmd/b@v1.2.3 && go install example.com/y@v2.0.0-rc.1",
+        );
+        let found: Vec<(&str, &str)> = targets
+            .iter()
+            .map(|(package, version, _)| (package.as_str(), version.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("example.com/x/cmd/a", "latest"),
+                ("example.com/x/cmd/b", "v1.2.3"),
+                ("example.com/y", "v2.0.0-rc.1"),
+            ]
+        );
+        assert_eq!(targets[2].2, "go install example.com/y@v2.0.0-rc.1");
+        assert!(parse_go_install("go install ./cmd/a").is_empty());
+        assert!(parse_go_install("go get example.com/x@latest").is_empty());
+    }
+
+    #[test]
+    fn targets_are_matched_against_main_packages() {
+        let mut index = RepoIndex::default();
+        index
+            .texts
+            .insert("go.mod".into(), "module example.com/x\n".to_string());
+        index.texts.insert(
+            "README.md".into(),
+            "```\n\
+             go install example.com/x/cmd/a@latest\n\
+             go install example.com/x/cmd/...@v1.4.0\n\
+             go install example.com/x/cmd/missing@latest\n\
+             go install example.com/x/cmd/a@v1.0.0\n\
+             go install other.org/tool@latest\n\
+             ```\n"
+                .to_string(),
+        );
+        let main_dirs = [PathBuf::from("cmd/a"), PathBuf::from("cmd/b")];
+        let detector = Detector::new(crate::Options::default()).unwrap();
+        let (details, targets) = detector.check_install_targets(&index, &main_dirs);
+
+        let found: Vec<(&str, &str, Vec<&str>)> = targets
+            .iter()
+            .map(|t| {
+                (
+                    t.package.as_str(),
+                    t.version.as_str(),
+                    t.dirs.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("example.com/x/cmd/a", "latest", vec!["./cmd/a"]),
+                (
+                    "example.com/x/cmd/...",
+                    "v1.4.0",
+                    vec!["./cmd/a", "./cmd/b"]
+                ),
+                ("example.com/x/cmd/missing", "latest", vec![]),
+                ("other.org/tool", "latest", vec![]),
+            ]
+        );
+        assert!(
+            details.contains(&"Install target: example.com/x/cmd/missing (unverified)".to_string())
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Classifies Go projects as CLI tools, libraries or tutorial collections.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), go_detector::DetectError> {
+//! use go_detector::{Detector, Options, ProjectType, Source};
+//!
+//! let detector = Detector::new(Options::default())?;
+//! let analysis = detector.analyze(&Source::parse("github.com/junegunn/fzf")).await?;
+//! if analysis.project_type == ProjectType::Cli {
+//!     println!("{}", serde_json::to_string(&analysis).unwrap());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod analysis;
+mod deps;
+mod error;
+mod fetch;
+mod hints;
+mod index;
+mod install;
+mod workflows;
+
+pub use analysis::{Analysis, ProjectType};
+pub use deps::{Dependencies, DependencyRisk};
+pub use error::{DetectError, ErrorKind, Stage};
+pub use hints::BuildHint;
+pub use install::InstallTarget;
+pub use workflows::WorkflowJob;
+
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "location", rename_all = "lowercase")]
+pub enum Source {
+    Git(String),
+    Archive(String),
+    Local(PathBuf),
+}
+
+impl Source {
+    /// Normalize a repository URL, archive URL or local directory
+    pub fn parse(input: &str) -> Self {
+        let url = input.trim();
+
+        if Path::new(url).is_dir() {
+            return Source::Local(PathBuf::from(url));
+        }
+
+        if url.ends_with(".tar.gz")
+            || url.ends_with(".tgz")
+            || url.ends_with(".zip")
+            || url.contains("/archive/")
+            || url.contains("/tarball/")
+            || url.contains("/releases/download/")
+        {
+            let normalized = if url.starts_with("http") {
+                url.to_string()
+            } else {
+                format!("https://{}", url)
+            };
+            Source::Archive(normalized)
+        } else {
+            let mut normalized = if url.starts_with("http") {
+                url.to_string()
+            } else {
+                format!("https://{}", url)
+            };
+            if !normalized.ends_with(".git") && !normalized.contains("github.com") {
+                normalized.push_str(".git");
+            }
+            Source::Git(normalized)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub resolve_timeout: Duration,
+    pub fetch_timeout: Duration,
+    pub extract_timeout: Duration,
+    pub analyze_timeout: Duration,
+    pub max_download_size: u64,
+    pub max_files: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            resolve_timeout: Duration::from_secs(30),
+            fetch_timeout: Duration::from_secs(300),
+            extract_timeout: Duration::from_secs(120),
+            analyze_timeout: Duration::from_secs(300),
+            max_download_size: 512 * 1024 * 1024,
+            max_files: 200_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Suppress progress messages on stderr
+    pub quiet: bool,
+    pub verbose: bool,
+    pub limits: Limits,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            quiet: true,
+            verbose: false,
+            limits: Limits::default(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Detector {
+    quiet: bool,
+    verbose: bool,
+    limits: Limits,
+    token: CancellationToken,
+    cli_patterns: Regex,
+    main_package_regex: Regex,
+    import_regex: Regex,
+    numbered_dir_regex: Regex,
+    usage_regex: Regex,
+    matrix_ref_regex: Regex,
+}
+
+impl Detector {
+    pub fn new(options: Options) -> Result<Self, DetectError> {
+        let cli_patterns = Regex::new(
+            r"(?i)(flag\.|os\.Args|cobra\.|spf13/cobra|urfave/cli|kingpin|pflag|cli\.App|\.Parse\(\)|\.String\(\)|\.Int\(\)|\.Bool\(\))",
+        )?;
+        let main_package_regex = Regex::new(r"^\s*package\s+main\s*$")?;
+        let import_regex =
+            Regex::new(r#"^\s*(?:import\s+(?:\(|"([^"]+)"|`([^`]+)`)|"([^"]+)"|`([^`]+)`)"#)?;
+        // Matches `00-hello`, `0.enum`, `042_two_sum`, `day-03`, `chapter1`, `ex12` ...
+        let numbered_dir_regex = Regex::new(
+            r"(?i)^(?:\d+(?:[-_.].*)?|(?:day|ch|chap|chapter|lesson|lec|lecture|ex|exercise|problem|part|step|week|task|unit)[-_]?\d+.*)$",
+        )?;
+
+        Ok(Self {
+            quiet: options.quiet,
+            verbose: options.verbose,
+            limits: options.limits,
+            token: CancellationToken::new(),
+            cli_patterns,
+            main_package_regex,
+            import_regex,
+            numbered_dir_regex,
+            usage_regex: Regex::new(r"\$ [a-zA-Z0-9_-]+\s")?,
+            matrix_ref_regex: Regex::new(r"^\$\{\{\s*matrix\.([A-Za-z0-9_-]+)\s*\}\}$")?,
+        })
+    }
+
+    pub(crate) fn log_info(&self, msg: &str) {
+        if !self.quiet {
+            eprintln!("\x1b[34m[INFO]\x1b[0m {}", msg);
+        }
+    }
+
+    pub(crate) fn log_verbose(&self, msg: &str) {
+        if self.verbose && !self.quiet {
+            eprintln!("\x1b[34m[VERBOSE]\x1b[0m {}", msg);
+        }
+    }
+
+    /// Fetch (unless local) and classify a project
+    pub async fn analyze(&self, source: &Source) -> Result<Analysis, DetectError> {
+        // Every run gets its own token so a timeout never poisons the detector
+        let session = Self {
+            token: CancellationToken::new(),
+            ..self.clone()
+        };
+        Ok(session.run(source).await?)
+    }
+
+    async fn run(&self, source: &Source) -> Result<Analysis> {
+        let temp_dir = TempDir::new()?;
+        let repo_dir = match source {
+            Source::Local(path) => path.clone(),
+            Source::Git(url) => {
+                let repo_dir = temp_dir.path().join("repo");
+                fs::create_dir_all(&repo_dir)?;
+                self.clone_git(url, &repo_dir).await?;
+                repo_dir
+            }
+            Source::Archive(url) => {
+                let repo_dir = temp_dir.path().join("repo");
+                fs::create_dir_all(&repo_dir)?;
+                self.download_archive(url, &repo_dir).await?;
+                repo_dir
+            }
+        };
+
+        // Analysis is CPU bound, so it is cancelled cooperatively via the token
+        let watchdog = {
+            let token = self.token.clone();
+            let after = self.limits.analyze_timeout;
+            tokio::spawn(async move {
+                tokio::time::sleep(after).await;
+                token.cancel();
+            })
+        };
+        let session = self.clone();
+        let result = tokio::task::spawn_blocking(move || session.analyze_repo(&repo_dir)).await;
+        watchdog.abort();
+
+        result?
+    }
+
+    pub(crate) async fn with_timeout<T, F>(
+        &self,
+        stage: Stage,
+        after: Duration,
+        fut: F,
+    ) -> Result<T>
+    where
+        F: std::future::Future<Output = Result<T>>,
+    {
+        match tokio::time::timeout(after, fut).await {
+            Ok(result) => result,
+            Err(_) => Err(DetectError::timeout(stage, after).into()),
+        }
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        if self.token.is_cancelled() {
+            Err(DetectError::timeout(Stage::Analyze, self.limits.analyze_timeout).into())
+        } else {
+            Ok(())
+        }
+    }
+}
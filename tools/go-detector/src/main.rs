@@ -1,16 +1,13 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use clap::{Arg, Command as ClapCommand};
-use indicatif::{ProgressBar, ProgressStyle};
-use rayon::prelude::*;
-use regex::Regex;
-use reqwest;
+use go_detector::{
+    Analysis, DetectError, Detector, ErrorKind, Limits, Options, ProjectType, Source,
+};
 use serde_json::json;
-use std::collections::HashSet;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use tempfile::TempDir;
-use tokio;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy)]
 enum OutputFormat {
@@ -19,868 +16,276 @@ enum OutputFormat {
     Simple,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum ProjectType {
-    Cli,
-    Library,
-    Unclear,
-}
-
-impl ProjectType {
-    fn as_str(&self) -> &'static str {
-        match self {
-            ProjectType::Cli => "cli",
-            ProjectType::Library => "library",
-            ProjectType::Unclear => "unclear",
-        }
-    }
-
-    fn exit_code(&self) -> i32 {
-        match self {
-            ProjectType::Cli => 0,
-            ProjectType::Library => 1,
-            ProjectType::Unclear => 2,
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FailPolicy {
+    Never,
+    Any,
+    All,
 }
 
-#[derive(Debug)]
-struct Analysis {
-    main_packages: usize,
-    directory_score: i32,
-    readme_score: i32,
-    executable_score: i32,
-    go_mod_score: i32,
-    binary_score: i32,
-    total_score: i32,
-    project_type: ProjectType,
-    confidence: &'static str,
-    details: Vec<String>,
-}
-
-#[derive(Debug)]
-struct GoFileInfo {
-    path: PathBuf,
-    package_name: String,
-    has_main_func: bool,
-    imports: Vec<String>,
-    has_cli_patterns: bool,
-}
-
-struct Detector {
+#[derive(Debug, Clone, Copy)]
+struct Cli {
     quiet: bool,
     verbose: bool,
     output_format: OutputFormat,
-    cli_patterns: Regex,
-    main_package_regex: Regex,
-    import_regex: Regex,
 }
 
-impl Detector {
-    fn new(quiet: bool, verbose: bool, output_format: OutputFormat) -> Result<Self> {
-        let cli_patterns = Regex::new(
-            r"(?i)(flag\.|os\.Args|cobra\.|spf13/cobra|urfave/cli|kingpin|pflag|cli\.App|\.Parse\(\)|\.String\(\)|\.Int\(\)|\.Bool\(\))",
-        )?;
-        let main_package_regex = Regex::new(r"^\s*package\s+main\s*$")?;
-        let import_regex =
-            Regex::new(r#"^\s*(?:import\s+(?:\(|"([^"]+)"|`([^`]+)`)|"([^"]+)"|`([^`]+)`)"#)?;
-
-        Ok(Self {
-            quiet,
-            verbose,
-            output_format,
-            cli_patterns,
-            main_package_regex,
-            import_regex,
-        })
-    }
-
-    fn log_info(&self, msg: &str) {
-        if !self.quiet {
-            eprintln!("\x1b[34m[INFO]\x1b[0m {}", msg);
-        }
-    }
-
-    fn log_verbose(&self, msg: &str) {
-        if self.verbose && !self.quiet {
-            eprintln!("\x1b[34m[VERBOSE]\x1b[0m {}", msg);
-        }
-    }
-
-    fn log_error(&self, msg: &str) {
-        eprintln!("\x1b[31m[ERROR]\x1b[0m {}", msg);
-    }
-
-    fn detect_url_type(&self, url: &str) -> Result<(&str, String)> {
-        let url = url.trim();
-
-        if url.ends_with(".tar.gz")
-            || url.ends_with(".tgz")
-            || url.ends_with(".zip")
-            || url.contains("/archive/")
-            || url.contains("/tarball/")
-            || url.contains("/releases/download/")
-        {
-            let normalized = if url.starts_with("http") {
-                url.to_string()
-            } else {
-                format!("https://{}", url)
-            };
-            Ok(("archive", normalized))
-        } else {
-            let mut normalized = if url.starts_with("http") {
-                url.to_string()
-            } else {
-                format!("https://{}", url)
-            };
-            if !normalized.ends_with(".git") && !normalized.contains("github.com") {
-                normalized.push_str(".git");
-            }
-            Ok(("git", normalized))
-        }
-    }
-
-    async fn download_archive(&self, url: &str, extract_dir: &Path) -> Result<()> {
-        self.log_info(&format!("Downloading archive: {}", url));
-
-        let pb = if !self.quiet {
-            let pb = ProgressBar::new_spinner();
-            pb.set_style(ProgressStyle::default_spinner().template("{spinner:.blue} {msg}")?);
-            pb.set_message("Downloading...");
-            Some(pb)
-        } else {
-            None
-        };
-
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(60))
-            .build()?;
-
-        let response = client.get(url).send().await?;
-        if !response.status().is_success() {
-            return Err(anyhow!("Failed to download: HTTP {}", response.status()));
-        }
-
-        let bytes = response.bytes().await?;
-
-        if let Some(pb) = &pb {
-            pb.set_message("Extracting...");
-        }
-
-        let archive_path = extract_dir.join("archive");
-        fs::write(&archive_path, &bytes)?;
-
-        // Detect archive format from content or extension
-        let extract_success = if url.ends_with(".zip") || self.is_zip_file(&archive_path)? {
-            self.try_extract_zip(&archive_path, extract_dir).is_ok()
-        } else {
-            self.try_extract_tar(&archive_path, extract_dir).is_ok()
-        };
-
-        fs::remove_file(&archive_path).ok();
-
-        if let Some(pb) = pb {
-            pb.finish_with_message("Done");
-        }
-
-        if extract_success {
-            Ok(())
-        } else {
-            Err(anyhow!("Failed to extract archive"))
-        }
-    }
-
-    fn is_zip_file(&self, path: &Path) -> Result<bool> {
-        let bytes = fs::read(path)?;
-        Ok(bytes.len() >= 4 && &bytes[0..4] == b"PK\x03\x04")
-    }
-
-    fn try_extract_tar(&self, archive_path: &Path, extract_dir: &Path) -> Result<()> {
-        let output = Command::new("tar")
-            .args(&[
-                "-xzf",
-                archive_path.to_str().unwrap(),
-                "--strip-components=1",
-            ])
-            .current_dir(extract_dir)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()?;
-
-        if output.success() {
-            Ok(())
-        } else {
-            // Try without compression flag
-            let output = Command::new("tar")
-                .args(&[
-                    "-xf",
-                    archive_path.to_str().unwrap(),
-                    "--strip-components=1",
-                ])
-                .current_dir(extract_dir)
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .status()?;
-
-            if output.success() {
-                Ok(())
-            } else {
-                Err(anyhow!("tar extraction failed"))
-            }
-        }
-    }
-
-    fn try_extract_zip(&self, archive_path: &Path, extract_dir: &Path) -> Result<()> {
-        let output = Command::new("unzip")
-            .args(&[
-                "-q",
-                "-o",
-                archive_path.to_str().unwrap(),
-                "-d",
-                extract_dir.to_str().unwrap(),
-            ])
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()?;
-
-        if output.success() {
-            self.handle_single_directory_extraction(extract_dir)?;
-            Ok(())
-        } else {
-            Err(anyhow!("unzip extraction failed"))
-        }
-    }
-
-    fn handle_single_directory_extraction(&self, extract_dir: &Path) -> Result<()> {
-        let entries: Vec<_> = fs::read_dir(extract_dir)?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
-            .collect();
-
-        if entries.len() == 1 {
-            let source = entries[0].path();
-            self.move_directory_contents(&source, extract_dir)?;
-            fs::remove_dir_all(&source).ok();
-        }
-        Ok(())
-    }
-
-    fn move_directory_contents(&self, source: &Path, dest: &Path) -> Result<()> {
-        for entry in fs::read_dir(source)? {
-            let entry = entry?;
-            let dest_path = dest.join(entry.file_name());
-            if entry.file_type()?.is_dir() {
-                fs::rename(entry.path(), dest_path)?;
-            } else {
-                fs::copy(entry.path(), dest_path)?;
-                fs::remove_file(entry.path()).ok();
-            }
-        }
-        Ok(())
-    }
-
-    fn clone_git(&self, url: &str, clone_dir: &Path) -> Result<()> {
-        self.log_info(&format!("Cloning repository: {}", url));
-
-        let pb = if !self.quiet {
-            let pb = ProgressBar::new_spinner();
-            pb.set_style(ProgressStyle::default_spinner().template("{spinner:.blue} {msg}")?);
-            pb.set_message("Cloning...");
-            Some(pb)
-        } else {
-            None
-        };
-
-        // Use shallow clone for better performance
-        let output = Command::new("git")
-            .args(&[
-                "clone",
-                "--depth=1",
-                "--filter=blob:none",
-                "--quiet",
-                url,
-                clone_dir.to_str().unwrap(),
-            ])
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()?;
-
-        if let Some(pb) = pb {
-            pb.finish_with_message("Done");
-        }
-
-        if output.success() {
-            Ok(())
-        } else {
-            Err(anyhow!("Git clone failed"))
-        }
-    }
-
-    fn analyze_go_file(&self, path: &Path) -> Result<GoFileInfo> {
-        let content = fs::read_to_string(path)?;
-        let lines: Vec<&str> = content.lines().collect();
-
-        let mut package_name = String::new();
-        let mut imports = Vec::new();
-        let mut has_main_func = false;
-        let has_cli_patterns = self.cli_patterns.is_match(&content);
-
-        for line in &lines {
-            if package_name.is_empty() && self.main_package_regex.is_match(line) {
-                package_name = "main".to_string();
-            } else if package_name.is_empty() && line.trim().starts_with("package ") {
-                package_name = line
-                    .trim()
-                    .strip_prefix("package ")
-                    .unwrap_or("")
-                    .trim()
-                    .to_string();
-            }
-
-            if line.contains("func main(") {
-                has_main_func = true;
-            }
+fn log_error(msg: &str) {
+    eprintln!("\x1b[31m[ERROR]\x1b[0m {}", msg);
+}
 
-            // Extract imports
-            if let Some(caps) = self.import_regex.captures(line) {
-                for i in 1..=4 {
-                    if let Some(import) = caps.get(i) {
-                        imports.push(import.as_str().to_string());
-                        break;
+fn output_results(analysis: &Analysis, url: &str, cli: &Cli) {
+    match cli.output_format {
+        OutputFormat::Json => {
+            let json = json!({
+                "url": url,
+                "type": analysis.project_type.as_str(),
+                "confidence": analysis.confidence,
+                "score": analysis.total_score,
+                "analysis": {
+                    "main_packages": analysis.main_packages,
+                    "directory_score": analysis.directory_score,
+                    "readme_score": analysis.readme_score,
+                    "executable_score": analysis.executable_score,
+                    "go_mod_score": analysis.go_mod_score,
+                    "binary_score": analysis.binary_score,
+                    "collection_score": analysis.collection_score
+                },
+                "details": analysis.details,
+                "build_hints": analysis.build_hints,
+                "install_targets": analysis.install_targets,
+                "workflows": analysis.workflows,
+                "dependencies": analysis.dependencies
+            });
+            println!("{}", json);
+        }
+        OutputFormat::Simple => {
+            println!("{}", analysis.project_type.as_str());
+        }
+        OutputFormat::Human => {
+            if !cli.quiet {
+                eprintln!("\n=== ANALYSIS RESULTS ===");
+                eprintln!(
+                    "Main packages: {} (×5 = {} points)",
+                    analysis.main_packages,
+                    analysis.main_packages * 5
+                );
+                eprintln!("Directory score: {} points", analysis.directory_score);
+                eprintln!("README score: {} points", analysis.readme_score);
+                eprintln!("Executable score: {} points", analysis.executable_score);
+                eprintln!("Go.mod score: {} points", analysis.go_mod_score);
+                eprintln!("Binary score: {} points", analysis.binary_score);
+                eprintln!("Collection score: {} points", analysis.collection_score);
+                eprintln!("Total score: {} points", analysis.total_score);
+
+                if !analysis.install_targets.is_empty() {
+                    eprintln!("\nInstall targets:");
+                    for target in &analysis.install_targets {
+                        if target.dirs.is_empty() {
+                            eprintln!("  • {}@{} (unverified)", target.package, target.version);
+                        } else {
+                            eprintln!(
+                                "  • {}@{} -> {}",
+                                target.package,
+                                target.version,
+                                target.dirs.join(", ")
+                            );
+                        }
                     }
                 }
-            }
-        }
-
-        Ok(GoFileInfo {
-            path: path.to_path_buf(),
-            package_name,
-            has_main_func,
-            imports,
-            has_cli_patterns,
-        })
-    }
-
-    fn find_go_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
-        let mut go_files = Vec::new();
-        self.find_go_files_recursive(dir, &mut go_files, 0)?;
-        Ok(go_files)
-    }
 
-    fn find_go_files_recursive(
-        &self,
-        dir: &Path,
-        files: &mut Vec<PathBuf>,
-        depth: usize,
-    ) -> Result<()> {
-        // Limit recursion depth to avoid issues
-        if depth > 10 {
-            return Ok(());
-        }
-
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_dir() && !self.is_ignored_dir(&path) {
-                self.find_go_files_recursive(&path, files, depth + 1)?;
-            } else if path.extension().map_or(false, |ext| ext == "go") && !self.is_test_file(&path)
-            {
-                files.push(path);
-            }
-        }
-        Ok(())
-    }
-
-    fn is_ignored_dir(&self, path: &Path) -> bool {
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            matches!(
-                name,
-                "vendor"
-                    | "node_modules"
-                    | ".git"
-                    | ".github"
-                    | ".gitignore"
-                    | "testdata"
-                    | "tests"
-                    | "_test"
-                    | "docs"
-                    | "documentation"
-                    | "examples"
-            )
-        } else {
-            false
-        }
-    }
-
-    fn is_test_file(&self, path: &Path) -> bool {
-        path.file_name()
-            .and_then(|n| n.to_str())
-            .map_or(false, |name| name.ends_with("_test.go"))
-    }
-
-    fn is_example_file(&self, path: &Path) -> bool {
-        let path_str = path.to_string_lossy().to_lowercase();
-        path_str.contains("/example")
-            || path_str.contains("/demo")
-            || path_str.contains("/sample")
-            || path_str.contains("_example.go")
-    }
-
-    fn check_main_packages(&self, repo_dir: &Path) -> Result<(usize, Vec<String>)> {
-        self.log_verbose("Checking for main packages...");
-
-        let go_files = self.find_go_files(repo_dir)?;
-
-        // Parallel processing for better performance
-        let file_infos: Vec<_> = go_files
-            .par_iter()
-            .filter_map(|file| {
-                if self.is_example_file(file) {
-                    return None;
+                if !analysis.build_hints.is_empty() {
+                    eprintln!("\nBuild hints:");
+                    for hint in &analysis.build_hints {
+                        eprintln!("  • [{}] {}", hint.file, hint.command);
+                    }
                 }
-                self.analyze_go_file(file).ok()
-            })
-            .collect();
-
-        let mut main_count = 0;
-        let mut details = Vec::new();
 
-        for info in file_infos {
-            if info.package_name == "main" && info.has_main_func {
-                let relative_path = info
-                    .path
-                    .strip_prefix(repo_dir)
-                    .unwrap_or(&info.path)
-                    .to_string_lossy();
-
-                self.log_verbose(&format!(
-                    "Found main package with main(): {}",
-                    relative_path
-                ));
-                details.push(format!("Main package: {}", relative_path));
-                main_count += 1;
-
-                // Use CLI patterns for additional scoring
-                if info.has_cli_patterns {
-                    details.push(format!("CLI patterns detected in: {}", relative_path));
+                if !analysis.workflows.is_empty() {
+                    eprintln!("\nCI builds:");
+                    for job in &analysis.workflows {
+                        let goos = if job.goos.is_empty() {
+                            "-".to_string()
+                        } else {
+                            job.goos.join(",")
+                        };
+                        let goarch = if job.goarch.is_empty() {
+                            "-".to_string()
+                        } else {
+                            job.goarch.join(",")
+                        };
+                        eprintln!(
+                            "  • [{}] {}: GOOS={} GOARCH={}{}",
+                            job.file,
+                            job.job,
+                            goos,
+                            goarch,
+                            if job.goreleaser { " (goreleaser)" } else { "" }
+                        );
+                    }
                 }
 
-                for import in &info.imports {
-                    if import.contains("cobra")
-                        || import.contains("urfave/cli")
-                        || import.contains("kingpin")
-                    {
-                        details.push(format!("CLI framework import: {}", import));
+                if !analysis.dependencies.risks.is_empty() {
+                    eprintln!("\nStatic build risks:");
+                    for risk in &analysis.dependencies.risks {
+                        eprintln!(
+                            "  • {}@{} ({}{})",
+                            risk.module,
+                            risk.version,
+                            risk.reason,
+                            if risk.direct { "" } else { ", indirect" }
+                        );
                     }
                 }
-            }
-        }
-        Ok((main_count, details))
-    }
-
-    fn check_directory_structure(&self, repo_dir: &Path) -> (i32, Vec<String>) {
-        self.log_verbose("Analyzing directory structure...");
-        let mut score = 0;
-        let mut details = Vec::new();
-
-        // Check for CLI-specific directories
-        if repo_dir.join("cmd").is_dir() {
-            self.log_verbose("Found 'cmd/' directory (+4 points)");
-            score += 4;
-            details.push("CLI structure: cmd/ directory".to_string());
-        }
-
-        if repo_dir.join("main.go").is_file() {
-            self.log_verbose("Found 'main.go' in root (+3 points)");
-            score += 3;
-            details.push("Entry point: main.go in root".to_string());
-        }
 
-        // Check for CLI binary directories
-        if repo_dir.join("bin").is_dir() {
-            score += 1;
-            details.push("Binary directory: bin/".to_string());
-        }
-
-        // Check for library-specific structure
-        let has_lib_dirs = repo_dir.join("pkg").is_dir()
-            || repo_dir.join("lib").is_dir()
-            || repo_dir.join("internal").is_dir();
-
-        if has_lib_dirs && !repo_dir.join("main.go").exists() && !repo_dir.join("cmd").exists() {
-            self.log_verbose("Library structure without main entry points (-2 points)");
-            score -= 2;
-            details.push("Library structure: pkg/lib/internal without main".to_string());
-        }
-
-        // Check for Makefile or build scripts
-        if repo_dir.join("Makefile").exists() || repo_dir.join("build.sh").exists() {
-            score += 1;
-            details.push("Build system present".to_string());
-        }
-
-        (score, details)
-    }
-
-    fn check_go_mod(&self, repo_dir: &Path) -> (i32, Vec<String>) {
-        self.log_verbose("Analyzing go.mod...");
-        let mut score = 0;
-        let mut details = Vec::new();
-
-        let go_mod_path = repo_dir.join("go.mod");
-        if !go_mod_path.exists() {
-            return (score, details);
-        }
-
-        if let Ok(content) = fs::read_to_string(&go_mod_path) {
-            // Check for CLI frameworks
-            let cli_deps = [
-                "github.com/spf13/cobra",
-                "github.com/urfave/cli",
-                "github.com/spf13/pflag",
-                "gopkg.in/alecthomas/kingpin",
-                "github.com/jessevdk/go-flags",
-            ];
-
-            for dep in &cli_deps {
-                if content.contains(dep) {
-                    score += 2;
-                    details.push(format!("CLI dependency: {}", dep));
-                    self.log_verbose(&format!("Found CLI dependency: {} (+2 points)", dep));
+                if cli.verbose && !analysis.details.is_empty() {
+                    eprintln!("\nDetection details:");
+                    for detail in &analysis.details {
+                        eprintln!("  • {}", detail);
+                    }
                 }
-            }
 
-            // Check module name pattern
-            if let Some(line) = content.lines().find(|l| l.starts_with("module ")) {
-                let module_name = line.strip_prefix("module ").unwrap_or("").trim();
-                if module_name.ends_with("/cmd")
-                    || module_name.contains("-cli")
-                    || module_name.contains("tool")
-                {
-                    score += 1;
-                    details.push("CLI-pattern module name".to_string());
-                }
+                eprintln!();
+                let (emoji, color) = match analysis.project_type {
+                    ProjectType::Cli => ("🔧", "\x1b[32m"),
+                    ProjectType::Library => ("📚", "\x1b[34m"),
+                    ProjectType::Unclear => ("❓", "\x1b[33m"),
+                    ProjectType::Collection => ("🎓", "\x1b[35m"),
+                };
+                eprintln!(
+                    "{} RESULT: {}{}\x1b[0m (Confidence: {})",
+                    emoji,
+                    color,
+                    analysis.project_type.as_str().to_uppercase(),
+                    analysis.confidence
+                );
+                eprintln!("URL: {}", url);
             }
         }
-
-        (score, details)
     }
+}
 
-    fn check_readme(&self, repo_dir: &Path) -> (i32, Vec<String>) {
-        self.log_verbose("Analyzing README...");
-        let mut score = 0;
-        let mut details = Vec::new();
-
-        let readme_patterns = [
-            "readme.md",
-            "README.md",
-            "readme.txt",
-            "README.txt",
-            "readme",
-            "README",
-        ];
-
-        for pattern in &readme_patterns {
-            let readme_path = repo_dir.join(pattern);
-            if readme_path.is_file() {
-                if let Ok(content) = fs::read_to_string(&readme_path) {
-                    let content_lower = content.to_lowercase();
-
-                    // Installation patterns
-                    if content_lower.contains("go install") && content_lower.contains("@latest") {
-                        self.log_verbose("Found binary installation instructions (+3 points)");
-                        score += 3;
-                        details.push("Installation: go install command".to_string());
-                    }
-
-                    if content_lower.contains("go get") && !content_lower.contains("import") {
-                        score += 2;
-                        details.push("Installation: go get command".to_string());
-                    }
-
-                    // CLI keywords
-                    let cli_keywords = [
-                        "cli tool",
-                        "command line",
-                        "command-line",
-                        "terminal",
-                        "console",
-                    ];
-                    for keyword in &cli_keywords {
-                        if content_lower.contains(keyword) {
-                            score += 1;
-                            details.push(format!("CLI keyword: {}", keyword));
-                            break;
-                        }
-                    }
-
-                    // Usage examples
-                    if Regex::new(r"\$ [a-zA-Z0-9_-]+\s")
-                        .unwrap()
-                        .is_match(&content)
-                    {
-                        self.log_verbose("Found command-line usage examples (+2 points)");
-                        score += 2;
-                        details.push("Usage: Command-line examples".to_string());
-                    }
-
-                    // Options/flags documentation
-                    if content.contains("--")
-                        || content.contains("flags:")
-                        || content.contains("options:")
-                    {
-                        score += 1;
-                        details.push("Documentation: CLI flags/options".to_string());
-                    }
-
-                    // Library indicators
-                    if content_lower.contains("import") && content_lower.contains("package") {
-                        score -= 1;
-                        details.push("Library indicator: import examples".to_string());
-                    }
+fn output_error(error: &DetectError, url: &str, cli: &Cli) {
+    let kind = error.kind;
+    let stage = error.stage;
+
+    match cli.output_format {
+        OutputFormat::Json => {
+            let json = json!({
+                "url": url,
+                "type": "error",
+                "error": {
+                    "kind": kind.as_str(),
+                    "stage": stage.map(|s| s.as_str()),
+                    "message": error.message
                 }
-                break;
-            }
+            });
+            println!("{}", json);
         }
-
-        (score, details)
-    }
-
-    fn check_executable_indicators(&self, repo_dir: &Path) -> (i32, Vec<String>) {
-        self.log_verbose("Checking for executable indicators...");
-        let mut score = 0;
-        let mut details = Vec::new();
-
-        let go_files = self.find_go_files(repo_dir).unwrap_or_default();
-        let mut cli_patterns_found = HashSet::new();
-
-        for file in go_files {
-            if self.is_example_file(&file) || self.is_test_file(&file) {
-                continue;
-            }
-
-            if let Ok(info) = self.analyze_go_file(&file) {
-                // Use the has_cli_patterns field
-                if info.has_cli_patterns && !cli_patterns_found.contains("cli_patterns") {
-                    self.log_verbose("Found CLI patterns in file (+2 points)");
-                    score += 2;
-                    details.push("CLI patterns detected in source".to_string());
-                    cli_patterns_found.insert("cli_patterns");
-                }
-
-                // Use imports for specific framework detection
-                for import in &info.imports {
-                    let framework = if import.contains("cobra") {
-                        "Cobra CLI framework"
-                    } else if import.contains("urfave/cli") {
-                        "Urfave CLI framework"
-                    } else if import.contains("kingpin") {
-                        "Kingpin CLI framework"
-                    } else if import.contains("flag") {
-                        "Standard flag package"
-                    } else {
-                        continue;
-                    };
-
-                    if !cli_patterns_found.contains(framework) {
-                        self.log_verbose(&format!("Found {}: {} (+1 point)", framework, import));
-                        score += 1;
-                        details.push(format!("CLI framework: {}", framework));
-                        cli_patterns_found.insert(framework);
-                    }
-                }
-            }
+        OutputFormat::Simple => {
+            println!("{}", kind.as_str());
         }
-
-        (score, details)
+        OutputFormat::Human => {}
     }
+    log_error(&format!("[{}] {}", kind.as_str(), error));
+}
 
-    fn check_binary_indicators(&self, repo_dir: &Path) -> (i32, Vec<String>) {
-        self.log_verbose("Checking for binary indicators...");
-        let mut score = 0;
-        let mut details = Vec::new();
-
-        // Check for GitHub Actions or CI that builds binaries
-        let github_dir = repo_dir.join(".github");
-        if github_dir.is_dir() {
-            for entry in
-                fs::read_dir(github_dir).unwrap_or_else(|_| fs::read_dir("/dev/null").unwrap())
-            {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    if path.is_file()
-                        && path
-                            .extension()
-                            .map_or(false, |ext| ext == "yml" || ext == "yaml")
-                    {
-                        if let Ok(content) = fs::read_to_string(&path) {
-                            if content.contains("go build") || content.contains("goreleaser") {
-                                score += 2;
-                                details.push("CI: Binary build detected".to_string());
-                                break;
-                            }
-                        }
+async fn run_batch(
+    detector: Detector,
+    urls: Vec<String>,
+    workers: usize,
+    policy: FailPolicy,
+    cli: Cli,
+) -> Result<i32> {
+    let total = urls.len();
+    let detector = Arc::new(detector);
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(workers.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for url in urls {
+        let permit = semaphore.clone().acquire_owned().await?;
+        let detector = detector.clone();
+        tasks.spawn(async move {
+            let _permit = permit;
+            let result = detector.analyze(&Source::parse(&url)).await;
+            match &result {
+                Ok(analysis) => match cli.output_format {
+                    OutputFormat::Json => output_results(analysis, &url, &cli),
+                    OutputFormat::Simple => {
+                        println!("{}\t{}", analysis.project_type.as_str(), url)
                     }
-                }
-            }
-        }
-
-        // Check for release files
-        if repo_dir.join("goreleaser.yml").exists() || repo_dir.join(".goreleaser.yml").exists() {
-            score += 2;
-            details.push("Release: GoReleaser config".to_string());
-        }
-
-        // Check for Dockerfile
-        if repo_dir.join("Dockerfile").exists() {
-            if let Ok(content) = fs::read_to_string(repo_dir.join("Dockerfile")) {
-                if content.contains("ENTRYPOINT") || content.contains("CMD") {
-                    score += 1;
-                    details.push("Container: Executable Docker image".to_string());
-                }
+                    OutputFormat::Human => eprintln!(
+                        "{} {} (Confidence: {})",
+                        analysis.project_type.as_str().to_uppercase(),
+                        url,
+                        analysis.confidence
+                    ),
+                },
+                Err(e) => output_error(e, &url, &cli),
             }
-        }
-
-        (score, details)
+            result.map(|a| a.project_type).map_err(|e| e.kind)
+        });
     }
 
-    fn analyze(&self, repo_dir: &Path) -> Result<Analysis> {
-        // Check if it's a Go project
-        if !repo_dir.join("go.mod").exists() {
-            let go_files = self.find_go_files(repo_dir)?;
-            if go_files.is_empty() {
-                return Err(anyhow!("Not a Go project (no go.mod or .go files found)"));
-            }
+    let mut types: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut errors: BTreeMap<ErrorKind, usize> = BTreeMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(Ok(project_type)) => *types.entry(project_type.as_str()).or_default() += 1,
+            Ok(Err(kind)) => *errors.entry(kind).or_default() += 1,
+            Err(_) => *errors.entry(ErrorKind::Internal).or_default() += 1,
         }
-
-        let (main_packages, mut all_details) = self.check_main_packages(repo_dir)?;
-        let (directory_score, mut dir_details) = self.check_directory_structure(repo_dir);
-        let (readme_score, mut readme_details) = self.check_readme(repo_dir);
-        let (executable_score, mut exec_details) = self.check_executable_indicators(repo_dir);
-        let (go_mod_score, mut mod_details) = self.check_go_mod(repo_dir);
-        let (binary_score, mut bin_details) = self.check_binary_indicators(repo_dir);
-
-        all_details.append(&mut dir_details);
-        all_details.append(&mut readme_details);
-        all_details.append(&mut exec_details);
-        all_details.append(&mut mod_details);
-        all_details.append(&mut bin_details);
-
-        let total_score = (main_packages as i32) * 5
-            + directory_score
-            + readme_score
-            + executable_score
-            + go_mod_score
-            + binary_score;
-
-        let (project_type, confidence) = if main_packages > 0 {
-            (ProjectType::Cli, "HIGH")
-        } else if total_score >= 6 {
-            (ProjectType::Cli, "HIGH")
-        } else if total_score >= 3 {
-            (ProjectType::Cli, "MEDIUM")
-        } else if total_score <= -3 {
-            (ProjectType::Library, "HIGH")
-        } else if total_score <= 0 {
-            (ProjectType::Library, "MEDIUM")
-        } else {
-            (ProjectType::Unclear, "LOW")
-        };
-
-        Ok(Analysis {
-            main_packages,
-            directory_score,
-            readme_score,
-            executable_score,
-            go_mod_score,
-            binary_score,
-            total_score,
-            project_type,
-            confidence,
-            details: all_details,
-        })
     }
 
-    fn output_results(&self, analysis: &Analysis, url: &str) {
-        match self.output_format {
-            OutputFormat::Json => {
-                let json = json!({
-                    "url": url,
-                    "type": analysis.project_type.as_str(),
-                    "confidence": analysis.confidence,
-                    "score": analysis.total_score,
-                    "analysis": {
-                        "main_packages": analysis.main_packages,
-                        "directory_score": analysis.directory_score,
-                        "readme_score": analysis.readme_score,
-                        "executable_score": analysis.executable_score,
-                        "go_mod_score": analysis.go_mod_score,
-                        "binary_score": analysis.binary_score
-                    },
-                    "details": analysis.details
-                });
-                println!("{}", json);
-            }
-            OutputFormat::Simple => {
-                println!("{}", analysis.project_type.as_str());
-            }
-            OutputFormat::Human => {
-                if !self.quiet {
-                    eprintln!("\n=== ANALYSIS RESULTS ===");
-                    eprintln!(
-                        "Main packages: {} (×5 = {} points)",
-                        analysis.main_packages,
-                        analysis.main_packages * 5
-                    );
-                    eprintln!("Directory score: {} points", analysis.directory_score);
-                    eprintln!("README score: {} points", analysis.readme_score);
-                    eprintln!("Executable score: {} points", analysis.executable_score);
-                    eprintln!("Go.mod score: {} points", analysis.go_mod_score);
-                    eprintln!("Binary score: {} points", analysis.binary_score);
-                    eprintln!("Total score: {} points", analysis.total_score);
-
-                    if self.verbose && !analysis.details.is_empty() {
-                        eprintln!("\nDetection details:");
-                        for detail in &analysis.details {
-                            eprintln!("  • {}", detail);
-                        }
-                    }
-
-                    eprintln!();
-                    let (emoji, color) = match analysis.project_type {
-                        ProjectType::Cli => ("🔧", "\x1b[32m"),
-                        ProjectType::Library => ("📚", "\x1b[34m"),
-                        ProjectType::Unclear => ("❓", "\x1b[33m"),
-                    };
-                    eprintln!(
-                        "{} RESULT: {}{}\x1b[0m (Confidence: {})",
-                        emoji,
-                        color,
-                        analysis.project_type.as_str().to_uppercase(),
-                        analysis.confidence
-                    );
-                    eprintln!("URL: {}", url);
+    let failed: usize = errors.values().sum();
+    match cli.output_format {
+        OutputFormat::Json => {
+            let json = json!({
+                "summary": {
+                    "total": total,
+                    "succeeded": total - failed,
+                    "failed": failed,
+                    "types": types,
+                    "errors": errors
+                        .iter()
+                        .map(|(kind, count)| (kind.as_str(), *count))
+                        .collect::<BTreeMap<_, _>>()
                 }
+            });
+            println!("{}", json);
+        }
+        _ => {
+            eprintln!("\n=== BATCH SUMMARY ===");
+            eprintln!(
+                "Total: {}  Succeeded: {}  Failed: {}",
+                total,
+                total - failed,
+                failed
+            );
+            for (project_type, count) in &types {
+                eprintln!("  {:<16} {}", project_type, count);
+            }
+            for (kind, count) in &errors {
+                eprintln!("  {:<16} {}", kind.as_str(), count);
             }
         }
     }
 
-    async fn detect(&self, url: &str) -> Result<Analysis> {
-        let (url_type, processed_url) = self.detect_url_type(url)?;
-
-        let temp_dir = TempDir::new()?;
-        let repo_dir = temp_dir.path().join("repo");
-        fs::create_dir_all(&repo_dir)?;
-
-        match url_type {
-            "git" => self.clone_git(&processed_url, &repo_dir)?,
-            "archive" => self.download_archive(&processed_url, &repo_dir).await?,
-            _ => return Err(anyhow!("Unknown URL type")),
-        }
-
-        self.analyze(&repo_dir)
+    let should_fail = match policy {
+        FailPolicy::Never => false,
+        FailPolicy::Any => failed > 0,
+        FailPolicy::All => total > 0 && failed == total,
+    };
+    if !should_fail {
+        return Ok(0);
     }
+
+    // A single failure kind keeps its own code, mixed failures collapse to the generic one
+    Ok(if errors.len() == 1 {
+        errors.keys().next().unwrap().exit_code()
+    } else {
+        ErrorKind::Internal.exit_code()
+    })
 }
 
 #[tokio::main]
@@ -890,8 +295,33 @@ async fn main() -> Result<()> {
         .about("Detects if a Go project is a CLI tool or library with improved accuracy")
         .arg(
             Arg::new("url")
-                .required(true)
-                .help("Git repository URL or archive URL"),
+                .required_unless_present("input")
+                .conflicts_with("input")
+                .help("Git repository URL, archive URL or local directory"),
+        )
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("FILE")
+                .help("File containing URLs to process (one per line)"),
+        )
+        .arg(
+            Arg::new("workers")
+                .short('w')
+                .long("workers")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("20")
+                .help("Number of parallel workers in batch mode"),
+        )
+        .arg(
+            Arg::new("fail-on")
+                .long("fail-on")
+                .value_name("POLICY")
+                .value_parser(["never", "any", "all"])
+                .default_value("all")
+                .help("Exit non-zero in batch mode when never/any/all items fail"),
         )
         .arg(
             Arg::new("quiet")
@@ -921,9 +351,56 @@ async fn main() -> Result<()> {
                 .action(clap::ArgAction::SetTrue)
                 .help("Show detailed analysis information"),
         )
+        .arg(
+            Arg::new("resolve-timeout")
+                .long("resolve-timeout")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("30")
+                .help("Timeout for reaching the remote: the archive request or git ls-remote"),
+        )
+        .arg(
+            Arg::new("fetch-timeout")
+                .long("fetch-timeout")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("300")
+                .help("Timeout for cloning or downloading"),
+        )
+        .arg(
+            Arg::new("extract-timeout")
+                .long("extract-timeout")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("120")
+                .help("Timeout for extracting archives"),
+        )
+        .arg(
+            Arg::new("analyze-timeout")
+                .long("analyze-timeout")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("300")
+                .help("Timeout for analyzing the source tree"),
+        )
+        .arg(
+            Arg::new("max-download-size")
+                .long("max-download-size")
+                .value_name("MB")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("512")
+                .help("Maximum archive size to download"),
+        )
+        .arg(
+            Arg::new("max-files")
+                .long("max-files")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("200000")
+                .help("Maximum number of files to walk"),
+        )
         .get_matches();
 
-    let url = matches.get_one::<String>("url").unwrap();
     let quiet = matches.get_flag("quiet");
     let verbose = matches.get_flag("verbose");
 
@@ -935,7 +412,66 @@ async fn main() -> Result<()> {
         OutputFormat::Human
     };
 
-    let detector = match Detector::new(quiet, verbose, output_format) {
+    let limits = Limits {
+        resolve_timeout: Duration::from_secs(*matches.get_one::<u64>("resolve-timeout").unwrap()),
+        fetch_timeout: Duration::from_secs(*matches.get_one::<u64>("fetch-timeout").unwrap()),
+        extract_timeout: Duration::from_secs(*matches.get_one::<u64>("extract-timeout").unwrap()),
+        analyze_timeout: Duration::from_secs(*matches.get_one::<u64>("analyze-timeout").unwrap()),
+        max_download_size: matches.get_one::<u64>("max-download-size").unwrap() * 1024 * 1024,
+        max_files: *matches.get_one::<usize>("max-files").unwrap(),
+    };
+    let cli = Cli {
+        quiet,
+        verbose,
+        output_format,
+    };
+
+    if let Some(input) = matches.get_one::<String>("input") {
+        let content = match fs::read_to_string(input) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Failed to read input file {}: {}", input, e);
+                std::process::exit(4);
+            }
+        };
+        let urls: Vec<String> = content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(String::from)
+            .collect();
+        let workers = *matches.get_one::<usize>("workers").unwrap();
+        let policy = match matches.get_one::<String>("fail-on").unwrap().as_str() {
+            "never" => FailPolicy::Never,
+            "any" => FailPolicy::Any,
+            _ => FailPolicy::All,
+        };
+
+        // Batch items never draw progress spinners
+        let options = Options {
+            quiet: true,
+            verbose,
+            limits,
+        };
+        let detector = match Detector::new(options) {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("Failed to initialize detector: {}", e);
+                std::process::exit(4);
+            }
+        };
+
+        let code = run_batch(detector, urls, workers, policy, cli).await?;
+        std::process::exit(code);
+    }
+
+    let url = matches.get_one::<String>("url").unwrap();
+    let options = Options {
+        quiet,
+        verbose,
+        limits,
+    };
+    let detector = match Detector::new(options) {
         Ok(d) => d,
         Err(e) => {
             eprintln!("Failed to initialize detector: {}", e);
@@ -943,14 +479,14 @@ async fn main() -> Result<()> {
         }
     };
 
-    match detector.detect(url).await {
+    match detector.analyze(&Source::parse(url)).await {
         Ok(analysis) => {
-            detector.output_results(&analysis, url);
+            output_results(&analysis, url, &cli);
             std::process::exit(analysis.project_type.exit_code());
         }
         Err(e) => {
-            detector.log_error(&format!("{}", e));
-            std::process::exit(3);
+            output_error(&e, url, &cli);
+            std::process::exit(e.kind.exit_code());
         }
     }
 }
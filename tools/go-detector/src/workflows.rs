@@ -0,0 +1,395 @@
+use crate::hints::{join_continuations, parse_go_build, split_commands, BuildHint};
+use crate::index::RepoIndex;
+use crate::Detector;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Build evidence from one GitHub Actions job
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WorkflowJob {
+    /// Workflow file, relative to the repository root
+    pub file: String,
+    pub job: String,
+    /// Runs `goreleaser/goreleaser-action` or the goreleaser binary
+    pub goreleaser: bool,
+    /// Number of `go build` invocations in `run` steps
+    pub go_builds: usize,
+    /// Target systems from `GOOS` settings and build matrices
+    pub goos: Vec<String>,
+    /// Target architectures from `GOARCH` settings and build matrices
+    pub goarch: Vec<String>,
+    /// `CGO_ENABLED` when it is set to a single value
+    pub cgo_enabled: Option<bool>,
+}
+
+impl WorkflowJob {
+    fn builds(&self) -> bool {
+        self.goreleaser || self.go_builds > 0
+    }
+
+    /// Builds for more than one GOOS/GOARCH pair, an unset one counting as one
+    fn cross_platform(&self) -> bool {
+        self.goos.len().max(1) * self.goarch.len().max(1) > 1
+    }
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn get<'a>(map: &'a Mapping, key: &str) -> Option<&'a Value> {
+    map.get(Value::String(key.to_string()))
+}
+
+/// `KEY: value` pairs of an `env:` block
+fn env_of(map: &Mapping) -> Vec<(String, String)> {
+    get(map, "env")
+        .and_then(Value::as_mapping)
+        .map(|env| {
+            env.iter()
+                .filter_map(|(k, v)| Some((scalar(k)?, scalar(v)?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Values each matrix key takes, including `include:` entries
+fn matrix_of(job: &Mapping) -> Vec<(String, Vec<String>)> {
+    let Some(matrix) = get(job, "strategy")
+        .and_then(Value::as_mapping)
+        .and_then(|s| get(s, "matrix"))
+        .and_then(Value::as_mapping)
+    else {
+        return Vec::new();
+    };
+
+    let mut values: Vec<(String, Vec<String>)> = Vec::new();
+    let mut add = |key: String, value: String| match values.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, list)) => list.push(value),
+        None => values.push((key, vec![value])),
+    };
+
+    for (key, value) in matrix {
+        let Some(key) = scalar(key) else {
+            continue;
+        };
+        match (key.as_str(), value) {
+            ("include", Value::Sequence(entries)) => {
+                for entry in entries.iter().filter_map(Value::as_mapping) {
+                    for (k, v) in entry {
+                        if let (Some(k), Some(v)) = (scalar(k), scalar(v)) {
+                            add(k, v);
+                        }
+                    }
+                }
+            }
+            ("exclude", _) => {}
+            (_, Value::Sequence(items)) => {
+                for item in items.iter().filter_map(scalar) {
+                    add(key.clone(), item);
+                }
+            }
+            (_, value) => {
+                if let Some(value) = scalar(value) {
+                    add(key, value);
+                }
+            }
+        }
+    }
+    values
+}
+
+impl Detector {
+    /// Literal values of a setting, resolving `${{ matrix.x }}` references
+    fn resolve(&self, value: &str, matrix: &[(String, Vec<String>)]) -> Vec<String> {
+        if let Some(caps) = self.matrix_ref_regex.captures(value) {
+            return matrix
+                .iter()
+                .find(|(key, _)| *key == caps[1])
+                .map(|(_, values)| values.clone())
+                .unwrap_or_default();
+        }
+        if value.contains("${{") || value.is_empty() {
+            return Vec::new();
+        }
+        vec![value.to_string()]
+    }
+
+    fn analyze_job(
+        &self,
+        file: &str,
+        name: &str,
+        job: &Mapping,
+        workflow_env: &[(String, String)],
+    ) -> (WorkflowJob, Vec<BuildHint>) {
+        let matrix = matrix_of(job);
+        let mut hints = Vec::new();
+        let mut goreleaser = false;
+
+        // Settings from workflow, job and step `env:` blocks and inline assignments
+        let mut settings: Vec<(String, String)> = workflow_env.to_vec();
+        settings.extend(env_of(job));
+
+        for step in get(job, "steps")
+            .and_then(Value::as_sequence)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_mapping)
+        {
+            if get(step, "uses")
+                .and_then(Value::as_str)
+                .is_some_and(|uses| uses.contains("goreleaser"))
+            {
+                goreleaser = true;
+            }
+            settings.extend(env_of(step));
+
+            let Some(run) = get(step, "run").and_then(Value::as_str) else {
+                continue;
+            };
+            for line in join_continuations(run) {
+                for command in split_commands(&line) {
+                    if command.starts_with("goreleaser ") || command == "goreleaser" {
+                        goreleaser = true;
+                    }
+                    if let Some(hint) = parse_go_build(&command, file) {
+                        settings.extend(hint.env.iter().filter_map(|assignment| {
+                            let (k, v) = assignment.split_once('=')?;
+                            Some((k.to_string(), v.to_string()))
+                        }));
+                        hints.push(hint);
+                    }
+                }
+            }
+        }
+
+        let collect = |name: &str| -> Vec<String> {
+            let mut values: BTreeSet<String> = settings
+                .iter()
+                .filter(|(k, _)| k == name)
+                .flat_map(|(_, v)| self.resolve(v, &matrix))
+                .collect();
+            // A bare `goos`/`goarch` matrix is as good as an env reference
+            if values.is_empty() {
+                values.extend(
+                    matrix
+                        .iter()
+                        .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+                        .flat_map(|(_, v)| v.iter().cloned()),
+                );
+            }
+            values.into_iter().collect()
+        };
+
+        let cgo = collect("CGO_ENABLED");
+        let cgo_enabled = match cgo.as_slice() {
+            [value] => match value.as_str() {
+                "0" | "false" => Some(false),
+                "1" | "true" => Some(true),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let summary = WorkflowJob {
+            file: file.to_string(),
+            job: name.to_string(),
+            goreleaser,
+            go_builds: hints.len(),
+            goos: collect("GOOS"),
+            goarch: collect("GOARCH"),
+            cgo_enabled,
+        };
+        (summary, hints)
+    }
+
+    /// Parse `.github/workflows/*.yml` for build steps, release actions and targets
+    pub(crate) fn check_workflows(
+        &self,
+        index: &RepoIndex,
+    ) -> (i32, Vec<String>, Vec<WorkflowJob>, Vec<BuildHint>) {
+        self.log_verbose("Analyzing GitHub Actions workflows...");
+        let mut details = Vec::new();
+        let mut jobs = Vec::new();
+        let mut hints = Vec::new();
+
+        for (path, content) in index.texts_sorted() {
+            if path.parent() != Some(Path::new(".github/workflows")) {
+                continue;
+            }
+            let file = path.to_string_lossy();
+            let workflow: Value = match serde_yaml::from_str(content) {
+                Ok(workflow) => workflow,
+                Err(e) => {
+                    self.log_verbose(&format!("Skipping invalid workflow {}: {}", file, e));
+                    continue;
+                }
+            };
+            let Some(workflow) = workflow.as_mapping() else {
+                continue;
+            };
+            let workflow_env = env_of(workflow);
+
+            for (name, job) in get(workflow, "jobs")
+                .and_then(Value::as_mapping)
+                .into_iter()
+                .flatten()
+            {
+                let (Some(name), Some(job)) = (scalar(name), job.as_mapping()) else {
+                    continue;
+                };
+                let (summary, found) = self.analyze_job(&file, &name, job, &workflow_env);
+                if summary.builds() {
+                    jobs.push(summary);
+                    hints.extend(found);
+                }
+            }
+        }
+
+        let mut score = 0;
+        if !jobs.is_empty() {
+            self.log_verbose("Found binary builds in CI workflows (+2 points)");
+            score += 2;
+            details.push("CI: Binary build detected".to_string());
+        }
+        if jobs.iter().any(|j| j.goreleaser) {
+            details.push("CI: GoReleaser action".to_string());
+        }
+        if jobs.iter().any(WorkflowJob::cross_platform) {
+            self.log_verbose("Found cross-platform build matrix (+1 point)");
+            score += 1;
+            details.push("CI: Cross-platform build matrix".to_string());
+        }
+        for job in &jobs {
+            let mut targets = Vec::new();
+            if !job.goos.is_empty() {
+                targets.push(format!("GOOS={}", job.goos.join(",")));
+            }
+            if !job.goarch.is_empty() {
+                targets.push(format!("GOARCH={}", job.goarch.join(",")));
+            }
+            if let Some(cgo) = job.cgo_enabled {
+                targets.push(format!("CGO_ENABLED={}", u8::from(cgo)));
+            }
+            if !targets.is_empty() {
+                details.push(format!(
+                    "CI target: {} ({}) {}",
+                    job.file,
+                    job.job,
+                    targets.join(" ")
+                ));
+            }
+        }
+
+        (score, details, jobs, hints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Options;
+
+    fn job(yaml: &str) -> WorkflowJob {
+        let detector = Detector::new(Options::default()).unwrap();
+        let job: Value = serde_yaml::from_str(yaml).unwrap();
+        let (summary, _) = detector.analyze_job(
+            ".github/workflows/release.yml",
+            "build",
+            job.as_mapping().unwrap(),
+            &[],
+        );
+        summary
+    }
+
+    #[test]
+    fn goos_only_matrix_is_cross_platform() {
+        let job = job(r#"
+strategy:
+  matrix:
+    goos: [linux, windows]
+steps:
+  - run: GOOS=${{ matrix.goos }} go build -o app .
+"#);
+        assert_eq!(job.goos, ["linux", "windows"]);
+        assert!(job.goarch.is_empty());
+        assert!(job.cross_platform());
+    }
+
+    #[test]
+    fn goarch_only_matrix_is_cross_platform() {
+        let job = job(r#"
+strategy:
+  matrix:
+    arch: [amd64, arm64]
+steps:
+  - run: go build .
+    env:
+      GOARCH: ${{ matrix.arch }}
+"#);
+        assert!(job.goos.is_empty());
+        assert_eq!(job.goarch, ["amd64", "arm64"]);
+        assert!(job.cross_platform());
+    }
+
+    #[test]
+    fn single_target_is_not_cross_platform() {
+        let job = job(r#"
+env:
+  GOOS: linux
+  GOARCH: amd64
+  CGO_ENABLED: 0
+steps:
+  - run: go build ./cmd/app
+"#);
+        assert_eq!(job.goos, ["linux"]);
+        assert_eq!(job.goarch, ["amd64"]);
+        assert_eq!(job.cgo_enabled, Some(false));
+        assert!(!job.cross_platform());
+    }
+
+    #[test]
+    fn include_adds_and_exclude_keeps_matrix_values() {
+        let job = job(r#"
+strategy:
+  matrix:
+    goos: [linux, darwin]
+    goarch: [amd64]
+    exclude:
+      - goos: darwin
+    include:
+      - goos: windows
+        goarch: arm64
+steps:
+  - run: go build .
+    env:
+      GOOS: ${{ matrix.goos }}
+      GOARCH: ${{matrix.goarch}}
+"#);
+        assert_eq!(job.goos, ["darwin", "linux", "windows"]);
+        assert_eq!(job.goarch, ["amd64", "arm64"]);
+        assert!(job.cross_platform());
+    }
+
+    #[test]
+    fn unresolved_expressions_are_ignored() {
+        let job = job(r#"
+steps:
+  - uses: goreleaser/goreleaser-action@v5
+  - run: go build .
+    env:
+      GOOS: ${{ inputs.os }}
+"#);
+        assert!(job.goreleaser);
+        assert_eq!(job.go_builds, 1);
+        assert!(job.goos.is_empty());
+        assert!(!job.cross_platform());
+    }
+}
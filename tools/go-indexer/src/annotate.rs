@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Context, Result};
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs::{rename, File};
+use std::future::Future;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The modules of a grouped or enricher output, a JSON array or one object
+/// per line, repository groups being flattened. The input is read once up
+/// front to check and count the modules, and again while they are looked
+/// up, never holding more than one item of it.
+pub struct Modules {
+    input: PathBuf,
+    pub count: usize,
+}
+
+pub fn read_modules(input: &Path) -> Result<Modules> {
+    let mut count = 0;
+    for module in each_module(input)? {
+        module?;
+        count += 1;
+    }
+    Ok(Modules {
+        input: input.to_path_buf(),
+        count,
+    })
+}
+
+/// Peek at the next byte that is not whitespace
+fn skip_whitespace(reader: &mut impl BufRead) -> std::io::Result<Option<u8>> {
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(at) => {
+                let byte = buf[at];
+                reader.consume(at);
+                return Ok(Some(byte));
+            }
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+/// The items of a JSON array, parsed one at a time
+struct ArrayItems<R> {
+    reader: R,
+    first: bool,
+    done: bool,
+}
+
+impl<R: BufRead> ArrayItems<R> {
+    fn item(&mut self) -> Result<Option<Value>> {
+        let mut next = skip_whitespace(&mut self.reader)?;
+        if !self.first {
+            match next {
+                Some(b',') => {
+                    self.reader.consume(1);
+                    next = skip_whitespace(&mut self.reader)?;
+                }
+                Some(b']') => return Ok(None),
+                Some(_) => return Err(anyhow!("Expected , or ] after an item")),
+                None => return Err(anyhow!("Unterminated array")),
+            }
+        } else if next == Some(b']') {
+            return Ok(None);
+        }
+        self.first = false;
+        if next.is_none() {
+            return Err(anyhow!("Unterminated array"));
+        }
+        Ok(Some(Value::deserialize(
+            &mut serde_json::Deserializer::from_reader(&mut self.reader),
+        )?))
+    }
+}
+
+impl<R: BufRead> Iterator for ArrayItems<R> {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.item().transpose();
+        self.done = !matches!(item, Some(Ok(_)));
+        item
+    }
+}
+
+/// Read the modules of `input` one item at a time
+fn each_module(input: &Path) -> Result<impl Iterator<Item = Result<Value>>> {
+    let file = File::open(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let mut reader = BufReader::new(file);
+    let input = input.display().to_string();
+    let items: Box<dyn Iterator<Item = Result<(String, Value)>>> =
+        if skip_whitespace(&mut reader)? == Some(b'[') {
+            reader.consume(1);
+            let array = ArrayItems {
+                reader,
+                first: true,
+                done: false,
+            };
+            let input = input.clone();
+            Box::new(array.enumerate().map(move |(n, item)| {
+                item.map(|item| (format!("Item {}", n + 1), item))
+                    .with_context(|| format!("Invalid JSON array in {}", input))
+            }))
+        } else {
+            Box::new(
+                reader
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+                    .map({
+                        let input = input.clone();
+                        move |(n, line)| {
+                            let item = serde_json::from_str(&line?).with_context(|| {
+                                format!("Invalid JSON on line {} of {}", n + 1, input)
+                            })?;
+                            Ok((format!("Line {}", n + 1), item))
+                        }
+                    }),
+            )
+        };
+
+    Ok(items.flat_map(move |item| {
+        let members = match item {
+            Ok((at, Value::Object(mut item))) => match item.remove("modules") {
+                Some(Value::Array(members)) => {
+                    members.into_iter().map(|m| (at.clone(), m)).collect()
+                }
+                Some(other) => {
+                    item.insert("modules".to_string(), other);
+                    vec![(at, Value::Object(item))]
+                }
+                None => vec![(at, Value::Object(item))],
+            },
+            Ok((at, other)) => vec![(at, other)],
+            Err(e) => return vec![Err(e)],
+        };
+        members
+            .into_iter()
+            .map(|(at, module)| {
+                if module.is_object() {
+                    Ok(module)
+                } else {
+                    Err(anyhow!(
+                        "{} of {} is not a JSON object: {}",
+                        at,
+                        input,
+                        module
+                    ))
+                }
+            })
+            .collect()
+    }))
+}
+
+/// The version a grouped record points at: an enricher record's `version`,
+/// else the newest release, prerelease or pseudo-version
+fn wanted_version(module: &Value) -> Option<String> {
+    [
+        "version",
+        "latest_release",
+        "latest_prerelease",
+        "latest_pseudo",
+    ]
+    .iter()
+    .find_map(|field| module.get(*field).and_then(Value::as_str))
+    .map(String::from)
+}
+
+/// Look every module up, at most `concurrent` at once, and write each to
+/// `output` as one JSON object per line in input order, with the fields of
+/// the result merged in or the error under `error_field`. `tally` sees
+/// every result for the summary, the number of failed lookups is returned.
+pub async fn annotate<T, F, Fut>(
+    modules: Modules,
+    output: &Path,
+    error_field: &str,
+    concurrent: usize,
+    progress: &ProgressBar,
+    lookup: F,
+    mut tally: impl FnMut(&T),
+) -> Result<usize>
+where
+    T: Serialize + Send + 'static,
+    F: Fn(String, Option<String>) -> Fut,
+    Fut: Future<Output = Result<T>> + Send + 'static,
+{
+    let spawn = |module: Value| {
+        let progress = progress.clone();
+        let result = module
+            .get("source")
+            .and_then(Value::as_str)
+            .map(|path| lookup(path.to_string(), wanted_version(&module)));
+        tokio::spawn(async move {
+            let result = match result {
+                Some(lookup) => lookup.await,
+                None => Err(anyhow!("Module without source")),
+            };
+            progress.inc(1);
+            (module, result)
+        })
+    };
+
+    // Written next to the final file and renamed, readers never see half of it
+    let partial = output.with_extension("tmp");
+    let mut writer = BufWriter::with_capacity(256 * 1024, File::create(&partial)?);
+    let mut pending = each_module(&modules.input)?;
+    // Lookups in input order, the oldest is awaited before another starts
+    let mut running = VecDeque::with_capacity(concurrent.max(1));
+    let mut failed = 0;
+    loop {
+        while running.len() < concurrent.max(1) {
+            match pending.next() {
+                Some(module) => running.push_back(spawn(module?)),
+                None => break,
+            }
+        }
+        let Some(handle) = running.pop_front() else {
+            break;
+        };
+        let (mut module, result) = handle.await?;
+        match result {
+            Ok(result) => {
+                tally(&result);
+                if let (Value::Object(module), Value::Object(fields)) =
+                    (&mut module, serde_json::to_value(result)?)
+                {
+                    module.extend(fields);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                module[error_field] = Value::String(format!("{:#}", e));
+            }
+        }
+        serde_json::to_writer(&mut writer, &module)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    drop(writer);
+    rename(&partial, output)?;
+    Ok(failed)
+}
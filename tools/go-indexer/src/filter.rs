@@ -0,0 +1,333 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use regex::Regex;
+
+/// Path elements of code that is not meant to be installed on its own
+const NON_PUBLIC_ELEMENTS: &[&str] = &[
+    "internal", "example", "examples", "test", "tests", "testdata", "vendor",
+];
+
+/// What to do with modules whose path ends in a major version suffix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MajorPolicy {
+    /// Keep every major version
+    All,
+    /// Keep only the highest major version of each module
+    Latest,
+    /// Drop `/v2` and above, keeping v0 and v1
+    Unsuffixed,
+}
+
+impl MajorPolicy {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "all" => Ok(MajorPolicy::All),
+            "latest" => Ok(MajorPolicy::Latest),
+            "unsuffixed" => Ok(MajorPolicy::Unsuffixed),
+            other => Err(anyhow!("Unknown major version policy: {}", other)),
+        }
+    }
+}
+
+/// Split a module path into the path without its major version suffix and
+/// the major version, `/vN` for N >= 2 and `gopkg.in`'s `.vN`
+pub fn major_suffix(path: &str) -> (&str, u64) {
+    let (parent, last) = path.rsplit_once('/').unwrap_or(("", path));
+    if path.starts_with("gopkg.in/") {
+        if let Some((name, major)) = last.rsplit_once(".v") {
+            if let Ok(major) = major.parse() {
+                return (&path[..parent.len() + 1 + name.len()], major);
+            }
+        }
+        return (path, 0);
+    }
+    match last.strip_prefix('v').map(str::parse::<u64>) {
+        Some(Ok(major)) if major >= 2 && !last.starts_with("v0") && !parent.is_empty() => {
+            (parent, major)
+        }
+        _ => (path, 0),
+    }
+}
+
+/// Whether the last path element, before any major version suffix, marks
+/// code not meant to be installed on its own. Modules that merely contain
+/// such an element higher up, like `github.com/org/test/cli`, are kept.
+fn is_non_public(path: &str) -> bool {
+    let (path, _) = major_suffix(path);
+    path.rsplit_once('/')
+        .is_some_and(|(_, last)| NON_PUBLIC_ELEMENTS.contains(&last))
+}
+
+/// Turn a shell-style glob (`*`, `?`) into an anchored, case-insensitive regex
+pub fn glob_regex(glob: &str) -> Result<Regex> {
+    let mut pattern = String::from("(?i)^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).with_context(|| format!("Invalid glob: {}", glob))
+}
+
+/// Which modules make it into the grouped output
+#[derive(Debug, Clone)]
+pub struct Filter {
+    /// Modules with no record on or after this date are dropped
+    pub last_seen_after: Option<NaiveDate>,
+    include_hosts: Vec<Regex>,
+    exclude_hosts: Vec<Regex>,
+    path_regex: Option<Regex>,
+    exclude_path_regex: Option<Regex>,
+    pub major: MajorPolicy,
+    exclude_non_public: bool,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            last_seen_after: None,
+            include_hosts: Vec::new(),
+            exclude_hosts: Vec::new(),
+            path_regex: None,
+            exclude_path_regex: None,
+            major: MajorPolicy::All,
+            exclude_non_public: false,
+        }
+    }
+}
+
+impl Filter {
+    pub fn with_hosts(mut self, include: &[String], exclude: &[String]) -> Result<Self> {
+        self.include_hosts = include
+            .iter()
+            .map(|g| glob_regex(g))
+            .collect::<Result<_>>()?;
+        self.exclude_hosts = exclude
+            .iter()
+            .map(|g| glob_regex(g))
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    pub fn with_path_regex(mut self, include: Option<&str>, exclude: Option<&str>) -> Result<Self> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).with_context(|| format!("Invalid path regex: {}", pattern))
+        };
+        self.path_regex = include.map(compile).transpose()?;
+        self.exclude_path_regex = exclude.map(compile).transpose()?;
+        Ok(self)
+    }
+
+    pub fn with_non_public_excluded(mut self, exclude: bool) -> Self {
+        self.exclude_non_public = exclude;
+        self
+    }
+
+    /// Whether any filter is set, for the configuration summary
+    pub fn is_active(&self) -> bool {
+        self.last_seen_after.is_some()
+            || !self.include_hosts.is_empty()
+            || !self.exclude_hosts.is_empty()
+            || self.path_regex.is_some()
+            || self.exclude_path_regex.is_some()
+            || self.major != MajorPolicy::All
+            || self.exclude_non_public
+    }
+
+    /// Filters that only need the module path, applied to every record
+    /// before it is sorted
+    pub fn accepts_path(&self, path: &str) -> bool {
+        let host = path.split('/').next().unwrap_or(path);
+        if !self.include_hosts.is_empty() && !self.include_hosts.iter().any(|g| g.is_match(host)) {
+            return false;
+        }
+        if self.exclude_hosts.iter().any(|g| g.is_match(host)) {
+            return false;
+        }
+        if self.path_regex.as_ref().is_some_and(|r| !r.is_match(path)) {
+            return false;
+        }
+        if self
+            .exclude_path_regex
+            .as_ref()
+            .is_some_and(|r| r.is_match(path))
+        {
+            return false;
+        }
+        if self.exclude_non_public && is_non_public(path) {
+            return false;
+        }
+        if self.major == MajorPolicy::Unsuffixed && major_suffix(path).1 >= 2 {
+            return false;
+        }
+        true
+    }
+
+    /// Filters that need the whole module, `last_seen` being the date of its
+    /// newest record
+    pub fn accepts_module(&self, last_seen: Option<NaiveDate>) -> bool {
+        match self.last_seen_after {
+            Some(after) => last_seen.is_some_and(|seen| seen >= after),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_public_modules_are_matched_on_their_last_element() {
+        let filter = Filter::default().with_non_public_excluded(true);
+        for dropped in [
+            "github.com/org/repo/internal",
+            "github.com/org/repo/examples",
+            "github.com/org/repo/example/v2",
+            "github.com/org/repo/test",
+            "github.com/org/repo/tests",
+            "github.com/org/repo/testdata",
+            "github.com/org/repo/vendor",
+        ] {
+            assert!(!filter.accepts_path(dropped), "{}", dropped);
+        }
+        for kept in [
+            "github.com/org/repo",
+            "github.com/org/test/cli",
+            "github.com/org/examples/tool",
+            "github.com/org/repo/internal/cmd",
+            "github.com/org/repo/testing",
+            "github.com/org/repo/v2",
+            "internal",
+        ] {
+            assert!(filter.accepts_path(kept), "{}", kept);
+        }
+        assert!(Filter::default().accepts_path("github.com/org/repo/internal"));
+    }
+
+    fn hosts(include: &[&str], exclude: &[&str]) -> Filter {
+        let strings = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        Filter::default()
+            .with_hosts(&strings(include), &strings(exclude))
+            .unwrap()
+    }
+
+    #[test]
+    fn host_globs_match_the_first_element_ignoring_case() {
+        let filter = hosts(&["github.com", "*.googlesource.com"], &[]);
+        assert!(filter.accepts_path("github.com/org/repo"));
+        assert!(filter.accepts_path("GitHub.COM/org/repo"));
+        assert!(filter.accepts_path("go.googlesource.com/tools"));
+        assert!(!filter.accepts_path("gitlab.com/org/repo"));
+        assert!(!filter.accepts_path("example.com/github.com/repo"));
+
+        let filter = hosts(&["git?ab.com"], &[]);
+        assert!(filter.accepts_path("gitlab.com/org/repo"));
+        assert!(!filter.accepts_path("gitxlab.com/org/repo"));
+
+        let filter = hosts(&[], &["GITHUB.*", "gopkg.in"]);
+        assert!(!filter.accepts_path("github.com/org/repo"));
+        assert!(!filter.accepts_path("github.io/org/repo"));
+        assert!(!filter.accepts_path("gopkg.in/yaml.v3"));
+        assert!(filter.accepts_path("gitlab.com/org/repo"));
+        assert!(filter.accepts_path("mygithub.com/org/repo"));
+
+        let filter = hosts(&["*.com"], &["gitlab.*"]);
+        assert!(filter.accepts_path("github.com/org/repo"));
+        assert!(!filter.accepts_path("gitlab.com/org/repo"));
+        assert!(!filter.accepts_path("gopkg.in/yaml.v3"));
+        assert!(filter.is_active());
+    }
+
+    #[test]
+    fn path_regexes_include_and_exclude() {
+        let filter = Filter::default()
+            .with_path_regex(Some("/cmd/"), None)
+            .unwrap();
+        assert!(filter.accepts_path("github.com/org/repo/cmd/tool"));
+        assert!(!filter.accepts_path("github.com/org/repo"));
+
+        let filter = Filter::default()
+            .with_path_regex(Some("^github\\.com/"), Some("/v[0-9]+$"))
+            .unwrap();
+        assert!(filter.accepts_path("github.com/org/repo"));
+        assert!(!filter.accepts_path("github.com/org/repo/v2"));
+        assert!(!filter.accepts_path("gitlab.com/org/repo"));
+
+        assert!(Filter::default().with_path_regex(None, Some("(")).is_err());
+    }
+
+    #[test]
+    fn unsuffixed_policy_drops_major_versions_from_two() {
+        let filter = Filter {
+            major: MajorPolicy::Unsuffixed,
+            ..Filter::default()
+        };
+        for dropped in [
+            "github.com/org/repo/v2",
+            "github.com/org/repo/v10",
+            "gopkg.in/yaml.v3",
+            "gopkg.in/user/pkg.v2",
+        ] {
+            assert!(!filter.accepts_path(dropped), "{}", dropped);
+        }
+        for kept in [
+            "github.com/org/repo",
+            "github.com/org/repo/v1",
+            "github.com/org/repo/v01",
+            "gopkg.in/check.v1",
+        ] {
+            assert!(filter.accepts_path(kept), "{}", kept);
+        }
+        assert!(MajorPolicy::parse("none").is_err());
+    }
+
+    #[test]
+    fn major_suffixes_need_a_canonical_version_from_two() {
+        assert_eq!(
+            major_suffix("github.com/org/repo/v2"),
+            ("github.com/org/repo", 2)
+        );
+        assert_eq!(
+            major_suffix("github.com/org/repo/v1"),
+            ("github.com/org/repo/v1", 0)
+        );
+        assert_eq!(
+            major_suffix("github.com/org/repo/v01"),
+            ("github.com/org/repo/v01", 0)
+        );
+        assert_eq!(
+            major_suffix("github.com/org/repo/v02"),
+            ("github.com/org/repo/v02", 0)
+        );
+        assert_eq!(
+            major_suffix("github.com/org/repo/v2x"),
+            ("github.com/org/repo/v2x", 0)
+        );
+        assert_eq!(major_suffix("v2"), ("v2", 0));
+        assert_eq!(major_suffix("gopkg.in/yaml.v3"), ("gopkg.in/yaml", 3));
+        assert_eq!(
+            major_suffix("gopkg.in/user/pkg.v1"),
+            ("gopkg.in/user/pkg", 1)
+        );
+        assert_eq!(major_suffix("gopkg.in/yaml"), ("gopkg.in/yaml", 0));
+    }
+
+    #[test]
+    fn modules_are_kept_when_seen_on_or_after_the_date() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d);
+        let filter = Filter {
+            last_seen_after: day(10),
+            ..Filter::default()
+        };
+        assert!(filter.accepts_module(day(10)));
+        assert!(filter.accepts_module(day(11)));
+        assert!(!filter.accepts_module(day(9)));
+        assert!(!filter.accepts_module(None));
+        assert!(Filter::default().accepts_module(None));
+        assert!(Filter::default().accepts_module(day(1)));
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// index.golang.org never returns more than this many records per page
+const MAX_LIMIT: usize = 2000;
+
+struct Record {
+    timestamp: DateTime<Utc>,
+    line: String,
+}
+
+/// Load every `*.jsonl`/`*.json` page in `dir`, ordered like the real index
+fn load_records(dir: &Path) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read fixture directory {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "jsonl" || e == "json"))
+        .collect();
+    files.sort();
+
+    for file in files {
+        let contents = std::fs::read_to_string(&file)?;
+        for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+            let json: Value = serde_json::from_str(line)
+                .with_context(|| format!("Invalid record in {}: {}", file.display(), line))?;
+            let timestamp = json
+                .get("Timestamp")
+                .and_then(|t| t.as_str())
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .with_context(|| format!("Record without Timestamp in {}", file.display()))?;
+            records.push(Record {
+                timestamp: timestamp.with_timezone(&Utc),
+                line: line.trim().to_string(),
+            });
+        }
+    }
+
+    // Overlapping recordings replay each record once
+    records.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.line.cmp(&b.line)));
+    records.dedup_by(|a, b| a.line == b.line);
+    Ok(records)
+}
+
+/// Answer `/index?since=...&limit=...` the way index.golang.org does:
+/// records with `Timestamp >= since`, oldest first, at most `limit`
+fn page(records: &[Record], target: &str) -> Result<String, String> {
+    let url = Url::parse(&format!("http://fixture{}", target)).map_err(|e| e.to_string())?;
+    if url.path() != "/index" {
+        return Err(format!("unknown path {}", url.path()));
+    }
+
+    let mut since = None;
+    let mut limit = MAX_LIMIT;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "since" => {
+                since = Some(
+                    DateTime::parse_from_rfc3339(&value)
+                        .map_err(|e| format!("invalid since: {}", e))?
+                        .with_timezone(&Utc),
+                )
+            }
+            "limit" => {
+                limit = value
+                    .parse::<usize>()
+                    .map_err(|e| format!("invalid limit: {}", e))?
+                    .min(MAX_LIMIT)
+            }
+            _ => {}
+        }
+    }
+
+    let start = since.map_or(0, |since| records.partition_point(|r| r.timestamp < since));
+    Ok(records[start..]
+        .iter()
+        .take(limit)
+        .map(|r| format!("{}\n", r.line))
+        .collect())
+}
+
+async fn handle(stream: TcpStream, records: Arc<Vec<Record>>, throttled: bool) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    // Drain the headers, the body (if any) is ignored
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let (status, headers, body) = match page(&records, target) {
+        _ if throttled => (
+            "429 Too Many Requests",
+            "Retry-After: 1\r\n",
+            "rate limited\n".to_string(),
+        ),
+        Ok(body) => ("200 OK", "", body),
+        Err(e) => ("400 Bad Request", "", format!("{}\n", e)),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    );
+    reader.get_mut().write_all(response.as_bytes()).await?;
+    reader.get_mut().shutdown().await?;
+    Ok(())
+}
+
+/// Serve recorded index pages until interrupted, answering every
+/// `throttle`th request (if not 0) with a 429 and `Retry-After: 1`
+pub async fn serve(dir: &Path, listen: &str, throttle: usize) -> Result<()> {
+    let records = Arc::new(load_records(dir)?);
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+
+    println!(
+        "🧪 Serving {} records from {} on http://{}/index",
+        records.len(),
+        dir.display(),
+        listener.local_addr()?
+    );
+
+    let requests = AtomicUsize::new(0);
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                let records = records.clone();
+                let count = requests.fetch_add(1, Ordering::Relaxed) + 1;
+                let throttled = throttle > 0 && count.is_multiple_of(throttle);
+                tokio::spawn(async move {
+                    if let Err(e) = handle(stream, records, throttled).await {
+                        eprintln!("Fixture request failed: {}", e);
+                    }
+                });
+            }
+        }
+    }
+    Ok(())
+}
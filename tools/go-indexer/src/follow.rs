@@ -0,0 +1,459 @@
+//! Tails the index: polls from the stored cursor, appends new records to the
+//! output and reports new versions of watched modules
+
+use crate::filter::glob_regex;
+use crate::paginate::{Outcome, Paginator};
+use crate::ratelimit::{backoff, RateLimiter};
+use crate::shutdown::spawn_signal_handler;
+use crate::state::{FollowState, StateDir};
+use crate::{
+    create_http_client, fetch_with_retry, index_page_url, rate_limiter, DEFAULT_INDEX_URL,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, SecondsFormat, TimeDelta, Utc};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use regex::Regex;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Url};
+use serde::Serialize;
+use serde_json::Value;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+/// A new version of a watched module, one JSON object per line on stdout
+#[derive(Debug, Serialize)]
+struct Event<'a> {
+    path: &'a str,
+    version: &'a str,
+    timestamp: &'a str,
+    /// The watch list entry it matched
+    watch: &'a str,
+}
+
+/// Module path globs of `--watch` and `--watch-file`
+struct WatchList(Vec<(String, Regex)>);
+
+impl WatchList {
+    fn load(mut globs: Vec<String>, files: &[String]) -> Result<Self> {
+        for file in files {
+            let contents = std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read watch list {}", file))?;
+            globs.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(String::from),
+            );
+        }
+        let entries = globs
+            .into_iter()
+            .map(|glob| {
+                let regex = glob_regex(&glob)?;
+                Ok((glob, regex))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self(entries))
+    }
+
+    fn matches(&self, path: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(_, re)| re.is_match(path))
+            .map(|(glob, _)| glob.as_str())
+    }
+}
+
+struct Follower {
+    client: Client,
+    limiter: Arc<RateLimiter>,
+    index_url: Url,
+    batch_size: usize,
+    max_retries: usize,
+    state: StateDir,
+    output: String,
+    watch: WatchList,
+    exec: Option<String>,
+    webhook: Option<Url>,
+    token: CancellationToken,
+}
+
+impl Follower {
+    /// Print `event`, then hand it to `--exec` and `--webhook`. Failures are
+    /// reported and the event is not retried.
+    async fn emit(&self, event: &Event<'_>) {
+        let json = serde_json::to_string(event).unwrap();
+        println!("{}", json);
+
+        if let Some(command) = &self.exec {
+            if let Err(e) = run_command(command, event, &json).await {
+                eprintln!(
+                    "⚠️  --exec failed for {}@{}: {:#}",
+                    event.path, event.version, e
+                );
+            }
+        }
+        if let Some(url) = &self.webhook {
+            if let Err(e) = self.post(url, &json).await {
+                eprintln!(
+                    "⚠️  --webhook failed for {}@{}: {:#}",
+                    event.path, event.version, e
+                );
+            }
+        }
+    }
+
+    async fn post(&self, url: &Url, json: &str) -> Result<()> {
+        let mut attempt = 1;
+        loop {
+            let sent = self
+                .client
+                .post(url.clone())
+                .header(CONTENT_TYPE, "application/json")
+                .body(json.to_string())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match sent {
+                Ok(_) => return Ok(()),
+                Err(e) if attempt >= self.max_retries => return Err(e.into()),
+                Err(_) => sleep(backoff(attempt)).await,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Fetch everything after the cursor, appending it to the output and
+    /// saving the cursor after each page. Returns the records appended and
+    /// the events emitted.
+    async fn poll(&self, paginator: &mut Paginator) -> Result<(usize, usize)> {
+        // The index has nothing past now, any bound beyond it will do
+        let until = Utc::now() + TimeDelta::days(1);
+        *paginator = paginator.clone().with_until(until);
+        let until = until.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+
+        let progress = ProgressBar::hidden();
+        let (mut appended, mut events) = (0, 0);
+        loop {
+            let url = index_page_url(&self.index_url, paginator.cursor(), &until, self.batch_size);
+            let body = tokio::select! {
+                _ = self.token.cancelled() => break,
+                body = fetch_with_retry(
+                    &self.client,
+                    &self.limiter,
+                    &url,
+                    self.max_retries,
+                    &progress,
+                ) => body?,
+            };
+
+            let batch = paginator.accept(&body, self.batch_size);
+            if !batch.invalid.is_empty() {
+                eprintln!("⚠️  Skipped {} invalid lines", batch.invalid.len());
+            }
+
+            if !batch.lines.is_empty() {
+                let mut output = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.output)
+                    .await
+                    .with_context(|| format!("Failed to open {}", self.output))?;
+                output
+                    .write_all((batch.lines.join("\n") + "\n").as_bytes())
+                    .await?;
+                output.flush().await?;
+                appended += batch.lines.len();
+            }
+
+            for line in &batch.lines {
+                // Accepted lines are records with all three fields
+                let record: Value = serde_json::from_str(line)?;
+                let field = |name: &str| record[name].as_str().unwrap_or_default();
+                if let Some(watch) = self.watch.matches(field("Path")) {
+                    self.emit(&Event {
+                        path: field("Path"),
+                        version: field("Version"),
+                        timestamp: field("Timestamp"),
+                        watch,
+                    })
+                    .await;
+                    events += 1;
+                }
+            }
+
+            // Only once the records are written and reported
+            self.state.save_follow(&FollowState {
+                cursor: paginator.cursor().to_string(),
+                seen: paginator.seen(),
+            })?;
+
+            match batch.outcome {
+                Outcome::Done => break,
+                Outcome::More => {}
+                Outcome::Stalled => {
+                    return Err(anyhow!(
+                        "Pagination stalled at {}: more than {} records share this timestamp",
+                        paginator.cursor(),
+                        self.batch_size
+                    ))
+                }
+            }
+        }
+        Ok((appended, events))
+    }
+}
+
+/// Run `command` with `sh -c`, the event as JSON on its stdin and in
+/// `GO_MODULE_*` variables. Its output goes to stderr, stdout is for events.
+async fn run_command(command: &str, event: &Event<'_>, json: &str) -> Result<()> {
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("GO_MODULE_PATH", event.path)
+        .env("GO_MODULE_VERSION", event.version)
+        .env("GO_MODULE_TIMESTAMP", event.timestamp)
+        .stdin(Stdio::piped())
+        .stdout(std::io::stderr())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that ignores its stdin may close it early
+        let _ = stdin.write_all(format!("{}\n", json).as_bytes()).await;
+    }
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(anyhow!("{} exited with {}", command, status));
+    }
+    Ok(())
+}
+
+/// `--since` as an RFC 3339 timestamp or a date
+fn parse_since(value: &str) -> Result<String> {
+    if DateTime::parse_from_rfc3339(value).is_ok() {
+        return Ok(value.to_string());
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .with_context(|| format!("Invalid since: {}", value))?;
+    Ok(format!("{}T00:00:00Z", date))
+}
+
+pub fn follow_command() -> Command {
+    Command::new("follow")
+        .about("Poll the index for new records, appending them and reporting watched modules")
+        .arg(
+            Arg::new("index-url")
+                .long("index-url")
+                .env("GO_INDEX_URL")
+                .value_name("URL")
+                .help("Index endpoint, for mirrors, proxies or a local fixture server")
+                .default_value(DEFAULT_INDEX_URL),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_name("FILE")
+                .help("Output file new records are appended to")
+                .default_value("go_index.jsonl"),
+        )
+        .arg(
+            Arg::new("state-dir")
+                .long("state-dir")
+                .value_name("DIR")
+                .help("Directory holding the follow cursor [default: <output>.state]"),
+        )
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .value_name("TIME")
+                .help("Where to start without a stored cursor, a timestamp or YYYY-MM-DD [default: the last complete day fetched, or now]"),
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .value_name("SECONDS")
+                .help("Seconds between polls")
+                .default_value("60"),
+        )
+        .arg(
+            Arg::new("once")
+                .long("once")
+                .help("Poll once and exit")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("Report new versions of matching module paths, e.g. github.com/cli/cli*"),
+        )
+        .arg(
+            Arg::new("watch-file")
+                .long("watch-file")
+                .value_name("FILE")
+                .action(ArgAction::Append)
+                .help("File of module path globs, one per line, # for comments"),
+        )
+        .arg(
+            Arg::new("exec")
+                .long("exec")
+                .value_name("COMMAND")
+                .help("Run with sh -c for each event, the event JSON on stdin and in GO_MODULE_PATH, GO_MODULE_VERSION, GO_MODULE_TIMESTAMP"),
+        )
+        .arg(
+            Arg::new("webhook")
+                .long("webhook")
+                .value_name("URL")
+                .help("POST each event as JSON to this URL"),
+        )
+        .arg(
+            Arg::new("batch-size")
+                .long("batch-size")
+                .value_name("N")
+                .help("Records per batch")
+                .default_value("2000"),
+        )
+        .arg(
+            Arg::new("retries")
+                .long("retries")
+                .value_name("N")
+                .help("Max retries per request")
+                .default_value("3"),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .value_name("SECONDS")
+                .help("Request timeout in seconds")
+                .default_value("30"),
+        )
+        .arg(
+            Arg::new("rate-limit")
+                .long("rate-limit")
+                .value_name("N")
+                .help("Max requests per second, 0 for no limit")
+                .default_value("25"),
+        )
+        .arg(
+            Arg::new("burst")
+                .long("burst")
+                .value_name("N")
+                .help("Requests allowed at once above the rate after a quiet spell")
+                .default_value("50"),
+        )
+}
+
+pub async fn follow(matches: &ArgMatches) -> Result<()> {
+    let index_url = matches.get_one::<String>("index-url").unwrap();
+    let index_url =
+        Url::parse(index_url).with_context(|| format!("Invalid index URL: {}", index_url))?;
+    let output = matches.get_one::<String>("output").unwrap().clone();
+    let state_dir = match matches.get_one::<String>("state-dir") {
+        Some(dir) => dir.clone(),
+        None => format!("{}.state", output.trim_end_matches(".jsonl")),
+    };
+    let interval = Duration::from_secs(matches.get_one::<String>("interval").unwrap().parse()?);
+    let timeout = Duration::from_secs(matches.get_one::<String>("timeout").unwrap().parse()?);
+    let strings = |id: &str| -> Vec<String> {
+        matches
+            .get_many::<String>(id)
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default()
+    };
+    let webhook = matches
+        .get_one::<String>("webhook")
+        .map(|url| Url::parse(url).with_context(|| format!("Invalid webhook URL: {}", url)))
+        .transpose()?;
+
+    let follower = Follower {
+        client: create_http_client(timeout).await,
+        limiter: rate_limiter(matches, 1)?,
+        index_url,
+        batch_size: matches.get_one::<String>("batch-size").unwrap().parse()?,
+        max_retries: matches.get_one::<String>("retries").unwrap().parse()?,
+        state: StateDir::open(&state_dir)?,
+        output,
+        watch: WatchList::load(strings("watch"), &strings("watch-file"))?,
+        exec: matches.get_one::<String>("exec").cloned(),
+        webhook,
+        token: CancellationToken::new(),
+    };
+
+    // A stored cursor wins, then --since, then where a batch run got to
+    let (start, from) = match follower.state.load_follow()? {
+        Some(stored) => (stored, "stored cursor"),
+        None => match (
+            matches.get_one::<String>("since"),
+            follower.state.last_complete()?,
+        ) {
+            (Some(since), _) => (
+                FollowState {
+                    cursor: parse_since(since)?,
+                    seen: Vec::new(),
+                },
+                "--since",
+            ),
+            (
+                None,
+                Some(crate::state::DayCheckpoint {
+                    cursor: Some(cursor),
+                    seen,
+                    ..
+                }),
+            ) => (FollowState { cursor, seen }, "last complete day"),
+            (None, _) => (
+                FollowState {
+                    cursor: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                    seen: Vec::new(),
+                },
+                "now",
+            ),
+        },
+    };
+    let mut paginator = Paginator::new(&start.cursor, start.seen, Utc::now())?;
+
+    eprintln!(
+        "👀 Following {} from {} ({}), {} watched globs",
+        follower.index_url,
+        start.cursor,
+        from,
+        follower.watch.0.len()
+    );
+    eprintln!("📂 State directory: {}", follower.state.path().display());
+    let once = matches.get_flag("once");
+    if !once {
+        spawn_signal_handler(follower.token.clone());
+    }
+
+    loop {
+        match follower.poll(&mut paginator).await {
+            Ok((0, _)) => {}
+            Ok((appended, events)) => eprintln!(
+                "🆕 {} new records, {} watched, cursor at {}",
+                appended,
+                events,
+                paginator.cursor()
+            ),
+            Err(e) if once => return Err(e),
+            Err(e) => eprintln!("❌ Poll failed: {:#}", e),
+        }
+        if once {
+            break;
+        }
+        tokio::select! {
+            _ = follower.token.cancelled() => break,
+            _ = sleep(interval) => {}
+        }
+    }
+
+    eprintln!("💾 Cursor saved at {}", paginator.cursor());
+    Ok(())
+}
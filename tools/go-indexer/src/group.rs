@@ -0,0 +1,544 @@
+use crate::filter::{major_suffix, Filter, MajorPolicy};
+use crate::proxy::zip_url;
+use crate::repo::repo_root;
+use crate::semver::{self, Latest};
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::{create_dir_all, remove_dir_all, rename, File};
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Records sorted in memory before a run is spilled to disk
+const RUN_RECORDS: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupFormat {
+    /// A single JSON array, the historical format
+    Json,
+    /// One object per line
+    Ndjson,
+}
+
+impl GroupFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "json" => Ok(GroupFormat::Json),
+            "ndjson" => Ok(GroupFormat::Ndjson),
+            other => Err(anyhow!("Unknown group format: {}", other)),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            GroupFormat::Json => "json",
+            GroupFormat::Ndjson => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// One entry per module path
+    Module,
+    /// One entry per inferred repository, listing its modules
+    Repo,
+}
+
+impl GroupBy {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "module" => Ok(GroupBy::Module),
+            "repo" => Ok(GroupBy::Repo),
+            other => Err(anyhow!("Unknown grouping: {}", other)),
+        }
+    }
+}
+
+/// What each module turns into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Records {
+    /// Its versions, grouped as configured
+    Grouped,
+    /// One `download`, `source`, `version` record as go-enricher reads it,
+    /// downloading from this proxy
+    Enricher { proxy: Url },
+}
+
+#[derive(Serialize)]
+struct GroupedResult {
+    /// Modules sharing a key are handled together: the repository root, or
+    /// the path without its major version suffix
+    #[serde(skip)]
+    key: String,
+    source: String,
+    /// Oldest to newest in semver order
+    versions: Vec<String>,
+    #[serde(flatten)]
+    latest: Latest,
+    /// Date of the newest record
+    #[serde(skip)]
+    last_seen: Option<NaiveDate>,
+}
+
+impl GroupedResult {
+    /// The version worth building: the newest release, else prerelease,
+    /// else pseudo-version, else the highest string
+    fn preferred_version(&self) -> Option<&str> {
+        self.latest
+            .latest_release
+            .as_deref()
+            .or(self.latest.latest_prerelease.as_deref())
+            .or(self.latest.latest_pseudo.as_deref())
+            .or(self.versions.last().map(String::as_str))
+    }
+}
+
+#[derive(Serialize)]
+struct EnricherRecord<'a> {
+    download: String,
+    source: &'a str,
+    version: &'a str,
+}
+
+#[derive(Serialize)]
+struct RepoGroup {
+    root: String,
+    modules: Vec<GroupedResult>,
+}
+
+/// One record of a sorted run, ordered by family and path so a family's
+/// modules, and a module's versions, end up next to each other
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Entry {
+    key: String,
+    source: String,
+    version: String,
+    /// `YYYY-MM-DD` of the record, empty if it had no timestamp
+    date: String,
+}
+
+impl Entry {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\n",
+            self.key, self.source, self.version, self.date
+        )
+    }
+
+    fn from_line(line: &str) -> Result<Self> {
+        let mut fields = line.splitn(4, '\t');
+        match (fields.next(), fields.next(), fields.next(), fields.next()) {
+            (Some(key), Some(source), Some(version), Some(date)) => Ok(Entry {
+                key: key.to_string(),
+                source: source.to_string(),
+                version: version.to_string(),
+                date: date.to_string(),
+            }),
+            _ => Err(anyhow!("Corrupt sort run line: {}", line)),
+        }
+    }
+}
+
+fn extract_field(obj: &Value, variants: &[&str]) -> Option<String> {
+    if let Value::Object(map) = obj {
+        for variant in variants {
+            if let Some(Value::String(val)) = map.get(*variant) {
+                return Some(val.clone());
+            }
+        }
+    }
+    None
+}
+
+/// Sort `entries` and spill them to the next run file
+fn write_run(dir: &Path, runs: &mut Vec<PathBuf>, entries: &mut Vec<Entry>) -> Result<()> {
+    entries.sort_unstable();
+    // A version republished later only moves its date forward
+    entries.dedup_by(|later, kept| {
+        let same = later.source == kept.source && later.version == kept.version;
+        if same {
+            kept.date = std::mem::take(&mut later.date);
+        }
+        same
+    });
+    let path = dir.join(format!("run_{:05}.tsv", runs.len()));
+    let mut writer = BufWriter::with_capacity(256 * 1024, File::create(&path)?);
+    for entry in entries.drain(..) {
+        writer.write_all(entry.to_line().as_bytes())?;
+    }
+    writer.flush()?;
+    runs.push(path);
+    Ok(())
+}
+
+/// Merge sorted runs, yielding each path with its distinct versions
+struct Merge {
+    runs: Vec<Lines<BufReader<File>>>,
+    heap: BinaryHeap<Reverse<(Entry, usize)>>,
+}
+
+impl Merge {
+    fn open(paths: &[PathBuf]) -> Result<Self> {
+        let mut merge = Merge {
+            runs: Vec::with_capacity(paths.len()),
+            heap: BinaryHeap::with_capacity(paths.len()),
+        };
+        for (index, path) in paths.iter().enumerate() {
+            merge
+                .runs
+                .push(BufReader::with_capacity(64 * 1024, File::open(path)?).lines());
+            merge.advance(index)?;
+        }
+        Ok(merge)
+    }
+
+    fn advance(&mut self, run: usize) -> Result<()> {
+        if let Some(line) = self.runs[run].next() {
+            self.heap.push(Reverse((Entry::from_line(&line?)?, run)));
+        }
+        Ok(())
+    }
+
+    fn next_group(&mut self) -> Result<Option<GroupedResult>> {
+        let Some(Reverse((first, run))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        self.advance(run)?;
+
+        let mut last_seen = first.date;
+        let mut versions = vec![first.version];
+        while self
+            .heap
+            .peek()
+            .is_some_and(|Reverse((next, _))| next.key == first.key && next.source == first.source)
+        {
+            let Reverse((next, run)) = self.heap.pop().unwrap();
+            self.advance(run)?;
+            versions.push(next.version);
+            last_seen = last_seen.max(next.date);
+        }
+
+        semver::sort(&mut versions);
+        versions.dedup();
+        Ok(Some(GroupedResult {
+            key: first.key,
+            source: first.source,
+            latest: semver::latest(&versions),
+            versions,
+            last_seen: NaiveDate::parse_from_str(&last_seen, "%Y-%m-%d").ok(),
+        }))
+    }
+}
+
+/// Collects consecutive modules sharing a family key, which the sort
+/// keeps together, so only one family is held in memory at a time
+#[derive(Default)]
+struct Families {
+    key: Option<String>,
+    members: Vec<GroupedResult>,
+}
+
+impl Families {
+    /// Add a module, handing back the previous family once it is complete
+    fn push(&mut self, result: GroupedResult) -> Option<(String, Vec<GroupedResult>)> {
+        let done = if self.key.as_deref() == Some(result.key.as_str()) {
+            None
+        } else {
+            self.finish()
+        };
+        self.key.get_or_insert_with(|| result.key.clone());
+        self.members.push(result);
+        done
+    }
+
+    fn finish(&mut self) -> Option<(String, Vec<GroupedResult>)> {
+        let key = self.key.take()?;
+        Some((key, std::mem::take(&mut self.members)))
+    }
+}
+
+/// Drop every module that has a higher major version in the same family
+fn keep_latest_majors(members: Vec<GroupedResult>) -> Vec<GroupedResult> {
+    let mut highest: HashMap<&str, u64> = HashMap::new();
+    for member in &members {
+        let (base, major) = major_suffix(&member.source);
+        let entry = highest.entry(base).or_default();
+        *entry = (*entry).max(major);
+    }
+    let keep: Vec<bool> = members
+        .iter()
+        .map(|member| {
+            let (base, major) = major_suffix(&member.source);
+            highest.get(base) == Some(&major)
+        })
+        .collect();
+    members
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(member, keep)| keep.then_some(member))
+        .collect()
+}
+
+/// Writes grouped results as they come out of the merge
+struct GroupWriter {
+    writer: BufWriter<File>,
+    format: GroupFormat,
+    written: usize,
+}
+
+impl GroupWriter {
+    fn write<T: Serialize>(&mut self, result: &T) -> Result<()> {
+        match self.format {
+            GroupFormat::Ndjson => {
+                serde_json::to_writer(&mut self.writer, result)?;
+                self.writer.write_all(b"\n")?;
+            }
+            // Same layout `to_string_pretty` gives the whole array
+            GroupFormat::Json => {
+                self.writer
+                    .write_all(if self.written == 0 { b"[\n" } else { b",\n" })?;
+                let pretty = serde_json::to_string_pretty(result)?;
+                for (i, line) in pretty.lines().enumerate() {
+                    if i > 0 {
+                        self.writer.write_all(b"\n")?;
+                    }
+                    write!(self.writer, "  {}", line)?;
+                }
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if self.format == GroupFormat::Json {
+            self.writer
+                .write_all(if self.written == 0 { b"[]" } else { b"\n]" })?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Group the output by module path with an external sort, so memory stays
+/// bounded by `RUN_RECORDS` however large the index is
+pub async fn process_output_file(
+    input_file: &str,
+    format: GroupFormat,
+    group_by: GroupBy,
+    records: &Records,
+    filter: &Filter,
+) -> Result<()> {
+    println!("🔄 Post-processing output file...");
+
+    let base = input_file.trim_end_matches(".jsonl");
+    let processed_file = format!("{}.processed.{}", base, format.extension());
+    let sort_dir = PathBuf::from(format!("{}.sort", base));
+    if sort_dir.exists() {
+        remove_dir_all(&sort_dir)?;
+    }
+    create_dir_all(&sort_dir)
+        .with_context(|| format!("Failed to create sort directory {}", sort_dir.display()))?;
+
+    let file = File::open(input_file)?;
+    let reader = BufReader::with_capacity(256 * 1024, file);
+
+    let source_fields = ["Path", "path"];
+    let version_fields = ["Version", "version"];
+
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .template("🔍 Processing {msg} {spinner:.green}")
+            .unwrap(),
+    );
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let mut line_count = 0;
+    let mut processed_count = 0;
+    let mut error_count = 0;
+    let mut filtered_count = 0;
+    let mut runs = Vec::new();
+    let mut entries = Vec::with_capacity(RUN_RECORDS);
+
+    for line_result in reader.lines() {
+        line_count += 1;
+
+        if line_count % 10000 == 0 {
+            pb.set_message(format!(
+                "{} lines | {} valid | {} filtered | {} errors",
+                line_count, processed_count, filtered_count, error_count
+            ));
+        }
+
+        let line = line_result?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let obj: Value = match serde_json::from_str(&line) {
+            Ok(obj) => obj,
+            Err(_) => {
+                error_count += 1;
+                continue;
+            }
+        };
+
+        let source = match extract_field(&obj, &source_fields) {
+            Some(s) => s,
+            None => {
+                error_count += 1;
+                continue;
+            }
+        };
+
+        let version = match extract_field(&obj, &version_fields) {
+            Some(v) => v,
+            None => {
+                error_count += 1;
+                continue;
+            }
+        };
+
+        // Tabs and newlines would corrupt the run files, neither is valid in
+        // a module path or version
+        if source.contains(['\t', '\n']) || version.contains(['\t', '\n']) {
+            error_count += 1;
+            continue;
+        }
+
+        // Path filters apply before anything is sorted or spilled
+        if !filter.accepts_path(&source) {
+            filtered_count += 1;
+            continue;
+        }
+
+        let date = extract_field(&obj, &["Timestamp", "timestamp"])
+            .and_then(|t| t.get(..10).map(String::from))
+            .unwrap_or_default();
+        let key = match (group_by, filter.major) {
+            (GroupBy::Repo, _) => repo_root(&source),
+            (GroupBy::Module, MajorPolicy::Latest) => major_suffix(&source).0.to_string(),
+            (GroupBy::Module, _) => source.clone(),
+        };
+        entries.push(Entry {
+            key,
+            source,
+            version,
+            date,
+        });
+        processed_count += 1;
+
+        if entries.len() >= RUN_RECORDS {
+            write_run(&sort_dir, &mut runs, &mut entries)?;
+        }
+    }
+    if !entries.is_empty() {
+        write_run(&sort_dir, &mut runs, &mut entries)?;
+    }
+
+    pb.finish_with_message(format!("✓ Processed {} lines", line_count));
+
+    println!(
+        "💾 Grouping {} sorted runs into processed output...",
+        runs.len()
+    );
+
+    if let Some(parent) = Path::new(&processed_file).parent() {
+        create_dir_all(parent)?;
+    }
+
+    // Written next to the final file and renamed, readers never see half of it
+    let partial_file = format!("{}.tmp", processed_file);
+    let mut writer = GroupWriter {
+        writer: BufWriter::with_capacity(256 * 1024, File::create(&partial_file)?),
+        format,
+        written: 0,
+    };
+    let mut merge = Merge::open(&runs)?;
+    let mut total_versions = 0;
+    let mut families = Families::default();
+    let mut skipped_modules = 0;
+    let mut unique_sources = 0;
+    let mut unescapable = 0;
+    let mut emit = |key: String, members: Vec<GroupedResult>| -> Result<()> {
+        let members = match filter.major {
+            MajorPolicy::Latest => keep_latest_majors(members),
+            _ => members,
+        };
+        unique_sources += members.len();
+        total_versions += members.iter().map(|m| m.versions.len()).sum::<usize>();
+        match (records, group_by) {
+            (Records::Enricher { proxy }, _) => {
+                for member in &members {
+                    let record = member.preferred_version().and_then(|version| {
+                        Some(EnricherRecord {
+                            download: zip_url(proxy, &member.source, version)?,
+                            source: &member.source,
+                            version,
+                        })
+                    });
+                    match record {
+                        Some(record) => writer.write(&record)?,
+                        None => unescapable += 1,
+                    }
+                }
+                Ok(())
+            }
+            (Records::Grouped, GroupBy::Module) => members.iter().try_for_each(|m| writer.write(m)),
+            (Records::Grouped, GroupBy::Repo) if members.is_empty() => Ok(()),
+            (Records::Grouped, GroupBy::Repo) => writer.write(&RepoGroup {
+                root: key,
+                modules: members,
+            }),
+        }
+    };
+    while let Some(result) = merge.next_group()? {
+        if !filter.accepts_module(result.last_seen) {
+            skipped_modules += 1;
+            continue;
+        }
+        if let Some((key, members)) = families.push(result) {
+            emit(key, members)?;
+        }
+    }
+    if let Some((key, members)) = families.finish() {
+        emit(key, members)?;
+    }
+    let written = writer.written;
+    writer.finish()?;
+    rename(&partial_file, &processed_file)?;
+    remove_dir_all(&sort_dir)?;
+
+    println!("✅ Post-processing complete!");
+    println!("📄 Processed file: {}", processed_file);
+    println!("📊 Unique sources: {}", unique_sources);
+    match records {
+        Records::Enricher { .. } => {
+            println!("🔗 Enricher records: {}", written);
+            if unescapable > 0 {
+                println!("⚠️  Modules without a download URL: {}", unescapable);
+            }
+        }
+        Records::Grouped if group_by == GroupBy::Repo => {
+            println!("📦 Repositories: {}", written);
+        }
+        Records::Grouped => {}
+    }
+    println!("🏷️  Total versions: {}", total_versions);
+    if filter.is_active() {
+        println!(
+            "🧹 Filtered out: {} records by path, {} modules by last seen date",
+            filtered_count, skipped_modules
+        );
+    }
+
+    Ok(())
+}
@@ -1,54 +1,114 @@
 //TODO: rewrite this properly, currently it is AI Garbage that does the bare minimum
 
+mod annotate;
+mod filter;
+mod fixture;
+mod follow;
+mod group;
+mod paginate;
+mod proxy;
+mod ratelimit;
+mod remote;
+mod repo;
+mod report;
+mod semver;
+mod shutdown;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod state;
+mod sumdb;
+mod window;
+
+use crate::filter::{Filter, MajorPolicy};
+use crate::group::{process_output_file, GroupBy, GroupFormat, Records};
+use crate::paginate::Paginator;
+use crate::ratelimit::{backoff, retry_after, RateLimiter};
+use crate::remote::Remote;
+use crate::report::{error_kind, Day, DayResult, Report, Truncated, EXIT_FAILED_DAYS};
+use crate::shutdown::{spawn_signal_handler, Interrupted, EXIT_INTERRUPTED};
+use crate::state::{DayCheckpoint, DayStatus, ExportState, StateDir};
+use crate::window::{Fetcher, Span, WindowEnd};
 use anyhow::{anyhow, Context, Result};
 use chrono::{NaiveDate, Utc};
-use clap::{Arg, Command};
-use indexmap::IndexMap;
+use clap::{Arg, ArgMatches, Command};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use reqwest::{Client, StatusCode};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::HashMap;
-use std::fs::{create_dir_all, File};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use reqwest::{Client, StatusCode, Url};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::create_dir_all;
+use std::io::SeekFrom;
 use std::path::Path;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::fs::{remove_file, OpenOptions};
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::mpsc::unbounded_channel;
 use tokio::time::sleep;
 use tokio_util::sync::CancellationToken;
 
+const DEFAULT_INDEX_URL: &str = "https://index.golang.org/index";
+
 #[derive(Debug, Clone)]
 struct Config {
+    index_url: Url,
     start_date: NaiveDate,
     end_date: NaiveDate,
     output_file: String,
+    state_dir: String,
     max_concurrent_days: usize,
     max_retries: usize,
     batch_size: usize,
+    max_window_requests: usize,
+    max_window_pages: usize,
     request_timeout: Duration,
+    /// Requests per second over all days, 0 for no limit
+    rate_limit: f64,
+    burst: usize,
+    limiter: Arc<RateLimiter>,
     dry_run: bool,
     resume_mode: bool,
     verbose: bool,
     process_output: bool,
+    group_format: GroupFormat,
+    group_by: GroupBy,
+    records: Records,
+    filter: Filter,
+    report: Option<String>,
+    max_failed_days: Option<usize>,
+    #[cfg(feature = "sqlite")]
+    sqlite: Option<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            index_url: Url::parse(DEFAULT_INDEX_URL).unwrap(),
             start_date: NaiveDate::from_ymd_opt(2019, 1, 1).unwrap(),
             end_date: Utc::now().date_naive(),
             output_file: "go_index.jsonl".to_string(),
+            state_dir: "go_index.state".to_string(),
             max_concurrent_days: 30,
             max_retries: 3,
             batch_size: 2000,
+            max_window_requests: 4,
+            max_window_pages: 1000,
             request_timeout: Duration::from_secs(30),
+            rate_limit: 25.0,
+            burst: 50,
+            limiter: Arc::new(RateLimiter::new(25.0, 50, 120)),
             dry_run: false,
-            resume_mode: false,
+            resume_mode: true,
             verbose: false,
             process_output: true,
+            group_format: GroupFormat::Json,
+            group_by: GroupBy::Module,
+            records: Records::Grouped,
+            filter: Filter::default(),
+            report: None,
+            max_failed_days: None,
+            #[cfg(feature = "sqlite")]
+            sqlite: None,
         }
     }
 }
@@ -64,18 +124,18 @@ struct IndexEntry {
     timestamp: Option<String>,
 }
 
-#[derive(Serialize)]
-struct GroupedResult {
-    source: String,
-    versions: Vec<String>,
-}
-
 #[derive(Clone)]
 struct Statistics {
     total_days: usize,
     completed_days: Arc<AtomicUsize>,
     total_records: Arc<AtomicUsize>,
     total_errors: Arc<AtomicUsize>,
+    error_kinds: Arc<Mutex<BTreeMap<&'static str, usize>>>,
+    truncated_days: Arc<AtomicUsize>,
+    duplicates: Arc<AtomicUsize>,
+    pages: Arc<AtomicUsize>,
+    /// Response bodies as received, after gzip decoding
+    body_bytes: Arc<AtomicU64>,
     start_time: Instant,
 }
 
@@ -86,6 +146,11 @@ impl Statistics {
             completed_days: Arc::new(AtomicUsize::new(0)),
             total_records: Arc::new(AtomicUsize::new(0)),
             total_errors: Arc::new(AtomicUsize::new(0)),
+            error_kinds: Arc::new(Mutex::new(BTreeMap::new())),
+            truncated_days: Arc::new(AtomicUsize::new(0)),
+            duplicates: Arc::new(AtomicUsize::new(0)),
+            pages: Arc::new(AtomicUsize::new(0)),
+            body_bytes: Arc::new(AtomicU64::new(0)),
             start_time: Instant::now(),
         }
     }
@@ -98,25 +163,63 @@ impl Statistics {
         self.total_records.fetch_add(count, Ordering::Relaxed);
     }
 
-    fn add_errors(&self, count: usize) {
+    fn add_errors(&self, kind: &'static str, count: usize) {
         self.total_errors.fetch_add(count, Ordering::Relaxed);
+        *self.error_kinds.lock().unwrap().entry(kind).or_default() += count;
+    }
+
+    /// A page as received, before duplicates are dropped
+    fn add_page(&self, bytes: usize, duplicates: usize) {
+        self.pages.fetch_add(1, Ordering::Relaxed);
+        self.body_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.duplicates.fetch_add(duplicates, Ordering::Relaxed);
     }
 
-    fn print_final(&self) {
+    fn increment_truncated(&self) {
+        self.truncated_days.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn print_final(&self, interrupted: bool) {
         let elapsed = self.start_time.elapsed();
         let completed = self.completed_days.load(Ordering::Relaxed);
         let records = self.total_records.load(Ordering::Relaxed);
         let errors = self.total_errors.load(Ordering::Relaxed);
-
-        println!("\n🎉 Processing Complete!");
+        let truncated = self.truncated_days.load(Ordering::Relaxed);
+        let duplicates = self.duplicates.load(Ordering::Relaxed);
+        let bytes = self.body_bytes.load(Ordering::Relaxed);
+
+        if interrupted {
+            println!("\n🛑 Processing Interrupted!");
+        } else {
+            println!("\n🎉 Processing Complete!");
+        }
         println!("┌────────────────────────────────────────────────────────────┐");
         println!("│ Final Statistics                                           │");
         println!("├────────────────────────────────────────────────────────────┤");
-        println!("│ {:<28} : {:>27} │", "Duration", format!("{:.2}s", elapsed.as_secs_f64()));
-        println!("│ {:<28} : {:>27} │", "Days processed", format!("{}/{}", completed, self.total_days));
+        println!(
+            "│ {:<28} : {:>27} │",
+            "Duration",
+            format!("{:.2}s", elapsed.as_secs_f64())
+        );
+        println!(
+            "│ {:<28} : {:>27} │",
+            "Days processed",
+            format!("{}/{}", completed, self.total_days)
+        );
         println!("│ {:<28} : {:>27} │", "Total records", records);
         println!("│ {:<28} : {:>27} │", "Errors", errors);
-        println!("│ {:<28} : {:>27} │", "Rate", format!("{:.0} records/sec", records as f64 / elapsed.as_secs_f64()));
+        println!("│ {:<28} : {:>27} │", "Truncated days", truncated);
+        println!("│ {:<28} : {:>27} │", "Duplicates dropped", duplicates);
+        println!(
+            "│ {:<28} : {:>27} │",
+            "Response bodies",
+            format!("{:.2} MB", bytes as f64 / 1_048_576.0)
+        );
+        println!(
+            "│ {:<28} : {:>27} │",
+            "Rate",
+            format!("{:.0} records/sec", records as f64 / elapsed.as_secs_f64())
+        );
         println!("└────────────────────────────────────────────────────────────┘");
     }
 }
@@ -131,221 +234,301 @@ async fn create_http_client(timeout: Duration) -> Client {
         .expect("Failed to create HTTP client")
 }
 
+/// A response status retrying does not fix, such as a proxy's 404 for an
+/// unknown module
+#[derive(Debug)]
+struct HttpStatus(StatusCode);
+
+impl std::fmt::Display for HttpStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP error: {}", self.0)
+    }
+}
+
+impl std::error::Error for HttpStatus {}
+
+/// A request that still failed once its retries ran out
+#[derive(Debug)]
+struct RetriesExhausted {
+    /// `rate_limited`, `server_error` or `network`, as counted in the report
+    kind: &'static str,
+    message: String,
+}
+
+impl std::fmt::Display for RetriesExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RetriesExhausted {}
+
 async fn fetch_with_retry(
     client: &Client,
+    limiter: &RateLimiter,
     url: &str,
     max_retries: usize,
     progress: &ProgressBar,
 ) -> Result<String> {
+    let body = fetch_bytes_with_retry(client, limiter, url, max_retries, progress).await?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// `fetch_with_retry` for binary responses such as checksum database tiles
+async fn fetch_bytes_with_retry(
+    client: &Client,
+    limiter: &RateLimiter,
+    url: &str,
+    max_retries: usize,
+    progress: &ProgressBar,
+) -> Result<Vec<u8>> {
     let mut attempt = 1;
-    let mut delay = Duration::from_secs(2);
 
     loop {
         progress.set_message(format!("Attempt {}/{}", attempt, max_retries));
 
-        match client.get(url).send().await {
+        let permit = limiter.acquire().await;
+        let wait = match client.get(url).send().await {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let text = response.text().await?;
-                    return Ok(text);
+                    let body = response.bytes().await?;
+                    limiter.succeeded();
+                    return Ok(body.to_vec());
                 }
                 StatusCode::TOO_MANY_REQUESTS => {
+                    limiter.rate_limited();
                     if attempt >= max_retries {
-                        return Err(anyhow!("Rate limited after {} attempts", max_retries));
+                        return Err(RetriesExhausted {
+                            kind: "rate_limited",
+                            message: format!("Rate limited after {} attempts", max_retries),
+                        }
+                        .into());
                     }
-                    progress.set_message("Rate limited, waiting...");
-                    sleep(Duration::from_secs(5)).await;
+                    // Every request holds back, not only this one
+                    let wait = match retry_after(response.headers()) {
+                        Some(wait) => {
+                            limiter.pause(wait);
+                            wait
+                        }
+                        None => backoff(attempt),
+                    };
+                    progress.set_message(format!(
+                        "Rate limited, waiting {:.1}s...",
+                        wait.as_secs_f64()
+                    ));
+                    wait
                 }
                 status if status.is_server_error() => {
                     if attempt >= max_retries {
-                        return Err(anyhow!(
-                            "Server error {} after {} attempts",
-                            status,
-                            max_retries
-                        ));
+                        return Err(RetriesExhausted {
+                            kind: "server_error",
+                            message: format!(
+                                "Server error {} after {} attempts",
+                                status, max_retries
+                            ),
+                        }
+                        .into());
                     }
                     progress.set_message(format!("Server error {}, retrying...", status));
-                    sleep(delay).await;
+                    retry_after(response.headers()).unwrap_or_else(|| backoff(attempt))
                 }
                 status => {
-                    return Err(anyhow!("HTTP error: {}", status));
+                    return Err(HttpStatus(status).into());
                 }
             },
             Err(e) => {
                 if attempt >= max_retries {
-                    return Err(anyhow!(
-                        "Network error after {} attempts: {}",
-                        max_retries,
-                        e
-                    ));
+                    return Err(RetriesExhausted {
+                        kind: "network",
+                        message: format!("Network error after {} attempts: {}", max_retries, e),
+                    }
+                    .into());
                 }
                 progress.set_message(format!("Network error, retrying... ({})", e));
-                sleep(delay).await;
+                backoff(attempt)
             }
-        }
-
+        };
+        drop(permit);
+        sleep(wait).await;
         attempt += 1;
-        delay = std::cmp::min(delay * 2, Duration::from_secs(60)); // Exponential backoff with cap
     }
 }
 
+/// Append the page query to the index URL, keeping any query it already has
+fn index_page_url(base: &Url, since: &str, until: &str, limit: usize) -> String {
+    let mut url = base.clone();
+    url.query_pairs_mut()
+        .append_pair("include", "all")
+        .append_pair("limit", &limit.to_string())
+        .append_pair("since", since)
+        .append_pair("until", until);
+    url.to_string()
+}
+
 async fn process_day(
     client: &Client,
     date: NaiveDate,
     config: &Config,
-    temp_dir: &Path,
+    state: &StateDir,
     stats: &Statistics,
     progress: &ProgressBar,
-    _token: CancellationToken,
-) -> Result<usize> {
-    let day_output = temp_dir.join(format!("day_{}.jsonl", date.format("%Y_%m_%d")));
-
-    // Check if already processed in resume mode
-    if config.resume_mode && day_output.exists() {
-        if let Ok(metadata) = tokio::fs::metadata(&day_output).await {
-            if metadata.len() > 0 {
-                progress.set_message("Already processed (skipping)");
-                progress.finish_with_message("✓ Skipped (already processed)");
-                stats.increment_completed();
-                return Ok(0);
-            }
-        }
+    token: CancellationToken,
+) -> Result<(DayResult, usize)> {
+    let day_output = state.day_file(date);
+    let checkpoint = state.load_day(date)?;
+
+    // Complete days are never fetched again
+    if let Some(DayCheckpoint {
+        status: DayStatus::Complete,
+        records,
+        ..
+    }) = checkpoint
+    {
+        progress.finish_with_message(format!("✓ Skipped ({} records already fetched)", records));
+        stats.increment_completed();
+        return Ok((DayResult::Skipped, records));
     }
 
-    let mut output_file = tokio::io::BufWriter::with_capacity(
-        64 * 1024,
-        OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&day_output)
-            .await?,
-    );
+    // Drop anything written after the last checkpoint, then continue from its cursor
+    let checkpoint = checkpoint.unwrap_or(DayCheckpoint {
+        status: DayStatus::Partial,
+        cursor: None,
+        seen: Vec::new(),
+        records: 0,
+        bytes: 0,
+        exported: 0,
+    });
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&day_output)
+        .await?;
+    file.set_len(checkpoint.bytes).await?;
+    let mut output_file = tokio::io::BufWriter::with_capacity(64 * 1024, file);
 
     let since = format!("{}T00:00:00Z", date.format("%Y-%m-%d"));
     let next_day = date.succ_opt().unwrap_or(date);
-    let until = format!("{}T00:00:00Z", next_day.format("%Y-%m-%d"));
-
-    let mut batch_num = 0;
-    let mut current_since = since.clone();
-    let mut empty_batches = 0;
-    let mut total_records = 0;
-    let date_str = date.format("%Y-%m-%d").to_string();
-
-    progress.set_message("Starting...");
-
-    loop {
-        //let url = format!(
-        //    "https://api.rv.pkgforge.dev/https://index.golang.org/index?include=all&limit={}&since={}&until={}",
-        //    config.batch_size, current_since, until
-        //);
-        let url = format!(
-            "https://index.golang.org/index?include=all&limit={}&since={}&until={}",
-            config.batch_size, current_since, until
-        );
-
-        progress.set_message(format!("Batch {} ({} records)", batch_num, total_records));
-
-        match fetch_with_retry(client, &url, config.max_retries, progress).await {
-            Ok(response_text) => {
-                if response_text.trim().is_empty() {
-                    empty_batches += 1;
-                    if empty_batches >= 3 {
-                        break;
-                    }
-                    continue;
-                }
-
-                empty_batches = 0;
-                let mut valid_lines = 0;
-                let mut new_timestamp = String::new();
-
-                // Process each line
-                for line in response_text.lines() {
-                    if line.trim().is_empty() {
-                        continue;
-                    }
-                    
-                    match serde_json::from_str::<Value>(line) {
-                        Ok(json) => {
-                            // Write valid JSON line to output
-                            output_file.write_all(line.as_bytes()).await?;
-                            output_file.write_all(b"\n").await?;
-                            valid_lines += 1;
-
-                            // Extract timestamp for next iteration
-                            if let Some(timestamp) = json.get("Timestamp").and_then(|t| t.as_str()) {
-                                new_timestamp = timestamp.to_string();
-                            }
-                        }
-                        Err(_) => {
-                            stats.add_errors(1);
-                            if config.verbose {
-                                eprintln!("Invalid JSON line: {}", line);
-                            }
-                        }
-                    }
-                }
-
-                if valid_lines == 0 {
-                    break;
-                }
-
-                total_records += valid_lines;
-                stats.add_records(valid_lines);
-
-                // Check stopping conditions
-                if valid_lines < config.batch_size {
-                    break;
-                }
-                
-                if new_timestamp.is_empty() {
-                    break;
-                }
-                
-                if new_timestamp == current_since {
-                    break; // No progress made
-                }
-
-                // Check if we've moved to the next day
-                if let Some(timestamp_date) = new_timestamp.split('T').next() {
-                    if timestamp_date != date_str {
-                        break;
-                    }
-                }
-
-                current_since = new_timestamp;
-                batch_num += 1;
-
-                // Safety limit to prevent infinite loops
-                if batch_num > 1000 {
-                    progress.set_message("Safety limit reached");
-                    break;
-                }
+    let paginator = Paginator::new(
+        checkpoint.cursor.as_deref().unwrap_or(&since),
+        checkpoint.seen.clone(),
+        next_day.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+    )?;
+    let mut cursor = paginator.cursor().to_string();
+    let mut seen = paginator.seen();
+    let mut total_records = checkpoint.records;
+    let mut bytes = checkpoint.bytes;
+
+    if checkpoint.cursor.is_some() {
+        progress.set_message(format!("Resuming from {}", cursor));
+    } else {
+        progress.set_message("Starting...");
+    }
 
-                // Brief pause to be nice to the API
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
-            Err(e) => {
-                stats.add_errors(1);
-                progress.finish_with_message(format!("✗ Failed: {}", e));
-                return Err(e);
+    // Heavy days are split into windows fetched concurrently, the records
+    // still arrive here in index order
+    let fetcher = Fetcher::new(
+        client.clone(),
+        config.clone(),
+        stats.clone(),
+        token,
+        progress.clone(),
+    );
+    let (chunks, mut received) = unbounded_channel();
+    let fetch = fetcher.run(paginator, Span::Day, chunks);
+    let write = async {
+        while let Some(chunk) = received.recv().await {
+            for line in &chunk.lines {
+                output_file.write_all(line.as_bytes()).await?;
+                output_file.write_all(b"\n").await?;
+                bytes += line.len() as u64 + 1;
             }
+            total_records += chunk.lines.len();
+            stats.add_records(chunk.lines.len());
+            cursor = chunk.paginator.cursor().to_string();
+            seen = chunk.paginator.seen();
+
+            // Checkpoint only what is safely on disk
+            output_file.flush().await?;
+            state.save_day(
+                date,
+                &DayCheckpoint {
+                    status: DayStatus::Partial,
+                    cursor: Some(cursor.clone()),
+                    seen: seen.clone(),
+                    records: total_records,
+                    bytes,
+                    exported: checkpoint.exported,
+                },
+            )?;
+            progress.set_message(format!("{} records, at {}", total_records, cursor));
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+    let (end, written) = tokio::join!(fetch, write);
+    written?;
+
+    match end {
+        WindowEnd::Complete => {}
+        WindowEnd::Interrupted => {
+            progress.finish_with_message(format!("⏸ Interrupted at {}", cursor));
+            return Err(Interrupted.into());
+        }
+        WindowEnd::Stalled => {
+            stats.add_errors("stalled", 1);
+            let e = anyhow!(
+                "Pagination stalled at {}: more than {} records share this timestamp",
+                cursor,
+                config.batch_size
+            );
+            progress.finish_with_message(format!("✗ Failed: {}", e));
+            return Err(e);
+        }
+        WindowEnd::Truncated { pages } => {
+            stats.increment_truncated();
+            let e = Truncated {
+                cursor: cursor.clone(),
+                pages,
+            };
+            progress.finish_with_message(format!("✂ {}", e));
+            return Err(e.into());
+        }
+        WindowEnd::Failed(e) => {
+            stats.add_errors(error_kind(&e), 1);
+            progress.finish_with_message(format!("✗ Failed: {}", e));
+            return Err(e);
         }
     }
 
-    output_file.flush().await?;
+    // A day that has not ended yet will get more records, keep it open for the next run
+    let status = if Utc::now().date_naive() > date {
+        DayStatus::Complete
+    } else {
+        DayStatus::Partial
+    };
+    state.save_day(
+        date,
+        &DayCheckpoint {
+            status,
+            cursor: Some(cursor),
+            seen,
+            records: total_records,
+            bytes,
+            exported: checkpoint.exported,
+        },
+    )?;
+
     stats.increment_completed();
     progress.finish_with_message(format!("✓ {} records", total_records));
-    Ok(total_records)
+    Ok((DayResult::Complete, total_records))
 }
 
 async fn process_days_parallel(
     config: &Config,
     dates: Vec<NaiveDate>,
-    temp_dir: &Path,
+    state: &StateDir,
     stats: &Statistics,
-) -> Result<()> {
+    token: &CancellationToken,
+) -> Result<Vec<Day>> {
     let client = create_http_client(config.request_timeout).await;
     let multi_progress = MultiProgress::new();
 
@@ -362,14 +545,17 @@ async fn process_days_parallel(
     main_pb.set_message("Processing days");
 
     let semaphore = Arc::new(tokio::sync::Semaphore::new(config.max_concurrent_days));
-    let token = CancellationToken::new();
     let mut handles = Vec::new();
 
     for date in dates {
-        let permit = semaphore.clone().acquire_owned().await?;
+        // Stop handing out days once shutdown is requested
+        let permit = tokio::select! {
+            _ = token.cancelled() => break,
+            permit = semaphore.clone().acquire_owned() => permit?,
+        };
         let client = client.clone();
         let config = config.clone();
-        let temp_dir = temp_dir.to_path_buf();
+        let state = state.clone();
         let stats = stats.clone();
         let token = token.clone();
 
@@ -381,44 +567,91 @@ async fn process_days_parallel(
 
         let handle = tokio::spawn(async move {
             let _permit = permit;
-            let result = process_day(&client, date, &config, &temp_dir, &stats, &pb, token).await;
+            let started = Instant::now();
+            let result = process_day(&client, date, &config, &state, &stats, &pb, token).await;
             main_pb.inc(1);
-            result
+            (date, result, started.elapsed())
         });
 
         handles.push(handle);
     }
 
     // Wait for all tasks to complete
-    let mut total_errors = 0;
+    let mut days = Vec::new();
+    let mut failures = Vec::new();
+    let mut interrupted = 0;
     for handle in handles {
-        match handle.await? {
-            Ok(_) => {}
-            Err(_) => total_errors += 1,
+        let (date, result, elapsed) = handle.await?;
+        let mut day = Day {
+            date,
+            result: DayResult::Complete,
+            records: 0,
+            seconds: elapsed.as_secs_f64(),
+            error: None,
+            truncated: None,
+        };
+        match result {
+            Ok((result, records)) => {
+                day.result = result;
+                day.records = records;
+            }
+            Err(e) if e.is::<Interrupted>() => {
+                day.result = DayResult::Interrupted;
+                interrupted += 1;
+            }
+            Err(e) => {
+                day.truncated = e.downcast_ref::<Truncated>().cloned();
+                day.result = if day.truncated.is_some() {
+                    DayResult::Truncated
+                } else {
+                    DayResult::Failed
+                };
+                day.error = Some(e.to_string());
+                failures.push((date, e));
+            }
         }
+        days.push(day);
     }
 
-    main_pb.finish_with_message("All days completed");
+    if token.is_cancelled() {
+        main_pb.abandon_with_message(format!("Interrupted ({} days in flight)", interrupted));
+    } else {
+        main_pb.finish_with_message("All days completed");
+    }
     multi_progress.clear()?;
 
-    if total_errors > 0 {
-        println!("⚠️  {} days failed to process", total_errors);
+    // Progress bars are cleared, so repeat why each day failed
+    if !failures.is_empty() {
+        println!("⚠️  {} days failed to process", failures.len());
+        for (date, e) in &failures {
+            println!("   {}: {}", date, e);
+        }
     }
 
-    Ok(())
+    Ok(days)
 }
 
-async fn combine_daily_files(
-    dates: &[NaiveDate],
-    temp_dir: &Path,
-    output_file: &str,
-    resume_mode: bool,
-) -> Result<usize> {
+/// Append everything fetched since the last export to the output dataset.
+/// The output is rebuilt from all day files when it is not the one last
+/// written, or has been changed since. Returns the lines appended and the
+/// offset they start at.
+async fn combine_daily_files(state: &StateDir, output_file: &str) -> Result<(usize, u64)> {
     println!("📦 Combining daily files into final output...");
 
-    if !resume_mode && Path::new(output_file).exists() {
+    let days = state.days()?;
+    let current_length = tokio::fs::metadata(output_file).await.ok().map(|m| m.len());
+    let append = state
+        .load_export()?
+        .is_some_and(|e| e.output == output_file && Some(e.length) == current_length);
+
+    if !append && Path::new(output_file).exists() {
         remove_file(output_file).await?;
     }
+    let appended_from = if append {
+        current_length.unwrap_or(0)
+    } else {
+        0
+    };
 
     if let Some(parent) = Path::new(output_file).parent() {
         create_dir_all(parent)?;
@@ -432,155 +665,46 @@ async fn combine_daily_files(
     let mut buffered_output = tokio::io::BufWriter::with_capacity(256 * 1024, final_output); // Smaller buffer of 256KB
 
     let mut total_lines = 0;
-    let pb = ProgressBar::new(dates.len() as u64);
+    let mut buffer = vec![0; 256 * 1024];
+    let pb = ProgressBar::new(days.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
             .template("📋 Combining [{bar:40.green/blue}] {pos}/{len} files {msg}")
             .unwrap(),
     );
 
-    for date in dates {
-        let path = temp_dir.join(format!("day_{}.jsonl", date.format("%Y_%m_%d")));
-
-        if path.exists() {
-            // Use smaller buffer and simpler approach
-            let contents = tokio::fs::read_to_string(&path).await?;
-            if !contents.is_empty() {
-                buffered_output.write_all(contents.as_bytes()).await?;
-                let line_count = contents.lines().count();
-                total_lines += line_count;
+    for (date, mut checkpoint) in days {
+        let from = if append { checkpoint.exported } else { 0 };
+        if checkpoint.bytes > from {
+            // Stream the new part of the day file, never the whole of it
+            let mut file = tokio::fs::File::open(state.day_file(date)).await?;
+            file.seek(SeekFrom::Start(from)).await?;
+            let mut file = file.take(checkpoint.bytes - from);
+            loop {
+                let read = file.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                buffered_output.write_all(&buffer[..read]).await?;
+                total_lines += buffer[..read].iter().filter(|b| **b == b'\n').count();
             }
         }
-        pb.set_message(format!("({} lines)", total_lines));
+        if checkpoint.exported != checkpoint.bytes {
+            checkpoint.exported = checkpoint.bytes;
+            state.save_day(date, &checkpoint)?;
+        }
+        pb.set_message(format!("({} new lines)", total_lines));
         pb.inc(1);
     }
 
     buffered_output.flush().await?;
-    pb.finish_with_message(format!("✓ {} total lines", total_lines));
-    Ok(total_lines)
-}
-
-// Post-processing functionality
-fn extract_field(obj: &Value, variants: &[&str]) -> Option<String> {
-    if let Value::Object(map) = obj {
-        for variant in variants {
-            if let Some(Value::String(val)) = map.get(*variant) {
-                return Some(val.clone());
-            }
-        }
-    }
-    None
-}
-
-fn remove_duplicates_preserve_order(versions: Vec<String>) -> Vec<String> {
-    let mut seen = IndexMap::new();
-    for version in versions {
-        seen.insert(version, ());
-    }
-    seen.into_keys().collect()
-}
-
-async fn process_output_file(input_file: &str) -> Result<()> {
-    println!("🔄 Post-processing output file...");
-
-    let processed_file = format!("{}.processed.json", input_file.trim_end_matches(".jsonl"));
-
-    let file = File::open(input_file)?;
-    let reader = BufReader::with_capacity(256 * 1024, file);
-
-    let mut grouped: HashMap<String, Vec<String>> = HashMap::with_capacity(10000);
-    let source_fields = ["Path", "path"];
-    let version_fields = ["Version", "version"];
-
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .template("🔍 Processing {msg} {spinner:.green}")
-            .unwrap(),
-    );
-    pb.enable_steady_tick(Duration::from_millis(100));
-
-    let mut line_count = 0;
-    let mut processed_count = 0;
-    let mut error_count = 0;
-
-    for line_result in reader.lines() {
-        line_count += 1;
-
-        if line_count % 10000 == 0 {
-            pb.set_message(format!(
-                "{} lines | {} valid | {} errors",
-                line_count, processed_count, error_count
-            ));
-        }
-
-        let line = line_result?;
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        let obj: Value = match serde_json::from_str(&line) {
-            Ok(obj) => obj,
-            Err(_) => {
-                error_count += 1;
-                continue;
-            }
-        };
-
-        let source = match extract_field(&obj, &source_fields) {
-            Some(s) => s,
-            None => {
-                error_count += 1;
-                continue;
-            }
-        };
-
-        let version = match extract_field(&obj, &version_fields) {
-            Some(v) => v,
-            None => {
-                error_count += 1;
-                continue;
-            }
-        };
-
-        grouped.entry(source).or_insert_with(Vec::new).push(version);
-        processed_count += 1;
-    }
-
-    pb.finish_with_message(format!("✓ Processed {} lines", line_count));
-
-    println!("📊 Grouping {} unique sources...", grouped.len());
-
-    let mut results: Vec<GroupedResult> = Vec::with_capacity(grouped.len());
-    for (source, versions) in grouped {
-        results.push(GroupedResult {
-            source,
-            versions: remove_duplicates_preserve_order(versions),
-        });
-    }
-
-    results.sort_unstable_by(|a, b| a.source.cmp(&b.source));
-
-    println!("💾 Writing processed output...");
-
-    if let Some(parent) = Path::new(&processed_file).parent() {
-        create_dir_all(parent)?;
-    }
-
-    let file = File::create(&processed_file)?;
-    let mut writer = BufWriter::with_capacity(256 * 1024, file);
-    let json_output = serde_json::to_string_pretty(&results)?;
-    writer.write_all(json_output.as_bytes())?;
-    writer.flush()?;
-
-    let total_versions: usize = results.iter().map(|r| r.versions.len()).sum();
-
-    println!("✅ Post-processing complete!");
-    println!("📄 Processed file: {}", processed_file);
-    println!("📊 Unique sources: {}", results.len());
-    println!("🏷️  Total versions: {}", total_versions);
-
-    Ok(())
+    drop(buffered_output);
+    state.save_export(&ExportState {
+        output: output_file.to_string(),
+        length: tokio::fs::metadata(output_file).await?.len(),
+    })?;
+    pb.finish_with_message(format!("✓ {} new lines", total_lines));
+    Ok((total_lines, appended_from))
 }
 
 fn generate_dates(start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
@@ -598,12 +722,183 @@ fn generate_dates(start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
     dates
 }
 
+/// The limiter of `--rate-limit` and `--burst`, with up to `max_in_flight`
+/// requests at once
+fn rate_limiter(matches: &ArgMatches, max_in_flight: usize) -> Result<Arc<RateLimiter>> {
+    Ok(Arc::new(RateLimiter::new(
+        matches.get_one::<String>("rate-limit").unwrap().parse()?,
+        matches.get_one::<String>("burst").unwrap().parse()?,
+        max_in_flight,
+    )))
+}
+
 fn build_cli() -> Command {
     let today: &'static str = Box::leak(Utc::now().format("%Y-%m-%d").to_string().into_boxed_str());
-    Command::new("go-indexer")
+    let cli = Command::new("go-indexer")
         .version("0.0.1")
         .author("Azathothas | QaidVoid")
         .about("Go index fetcher from index.golang.org")
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("serve-fixture")
+                .about("Serve recorded index pages locally, for offline testing")
+                .arg(
+                    Arg::new("dir")
+                        .long("dir")
+                        .value_name("DIR")
+                        .required(true)
+                        .help("Directory of recorded index pages (*.jsonl)"),
+                )
+                .arg(
+                    Arg::new("listen")
+                        .long("listen")
+                        .value_name("ADDR")
+                        .help("Address to listen on")
+                        .default_value("127.0.0.1:8080"),
+                )
+                .arg(
+                    Arg::new("throttle")
+                        .long("throttle")
+                        .value_name("N")
+                        .help("Answer every Nth request with 429 and Retry-After: 1, 0 never")
+                        .default_value("0"),
+                ),
+        )
+        .subcommand(follow::follow_command())
+        .subcommand(
+            Command::new("proxy")
+                .about("Look grouped modules up on a module proxy: versions, @latest, origin, retractions")
+                .arg(
+                    Arg::new("input")
+                        .long("input")
+                        .value_name("FILE")
+                        .required(true)
+                        .help("Grouped or enricher output, a JSON array or one object per line"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Output file, one object per line [default: <input>.proxy.jsonl]"),
+                )
+                .arg(
+                    Arg::new("proxy-url")
+                        .long("proxy-url")
+                        .env("GOPROXY")
+                        .value_name("URL")
+                        .help("Module proxy, the first proxy of a GOPROXY list, file:// for a local directory")
+                        .default_value(proxy::DEFAULT_PROXY_URL),
+                )
+                .arg(
+                    Arg::new("concurrent")
+                        .long("concurrent")
+                        .value_name("N")
+                        .help("Max modules looked up at once")
+                        .default_value("16"),
+                )
+                .arg(
+                    Arg::new("retries")
+                        .long("retries")
+                        .value_name("N")
+                        .help("Max retries per request")
+                        .default_value("3"),
+                )
+                .arg(
+                    Arg::new("rate-limit")
+                        .long("rate-limit")
+                        .value_name("N")
+                        .help("Max requests per second, 0 for no limit")
+                        .default_value("25"),
+                )
+                .arg(
+                    Arg::new("burst")
+                        .long("burst")
+                        .value_name("N")
+                        .help("Requests allowed at once above the rate after a quiet spell")
+                        .default_value("50"),
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .help("Request timeout in seconds")
+                        .default_value("30"),
+                ),
+        )
+        .subcommand(
+            Command::new("sumdb")
+                .about("Verify the versions of grouped modules are recorded in the checksum database")
+                .arg(
+                    Arg::new("input")
+                        .long("input")
+                        .value_name("FILE")
+                        .required(true)
+                        .help("Grouped, enricher or proxy output, a JSON array or one object per line"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Output file, one object per line [default: <input>.sumdb.jsonl]"),
+                )
+                .arg(
+                    Arg::new("sumdb-key")
+                        .long("sumdb-key")
+                        .env("GOSUMDB")
+                        .value_name("KEY")
+                        .required(true)
+                        .help("Verifier key the tree heads are signed with, optionally followed by the URL as in GOSUMDB"),
+                )
+                .arg(
+                    Arg::new("sumdb-url")
+                        .long("sumdb-url")
+                        .value_name("URL")
+                        .help("Checksum database, file:// for a local directory [default: https://<key name>]"),
+                )
+                .arg(
+                    Arg::new("concurrent")
+                        .long("concurrent")
+                        .value_name("N")
+                        .help("Max modules verified at once")
+                        .default_value("16"),
+                )
+                .arg(
+                    Arg::new("retries")
+                        .long("retries")
+                        .value_name("N")
+                        .help("Max retries per request")
+                        .default_value("3"),
+                )
+                .arg(
+                    Arg::new("rate-limit")
+                        .long("rate-limit")
+                        .value_name("N")
+                        .help("Max requests per second, 0 for no limit")
+                        .default_value("25"),
+                )
+                .arg(
+                    Arg::new("burst")
+                        .long("burst")
+                        .value_name("N")
+                        .help("Requests allowed at once above the rate after a quiet spell")
+                        .default_value("50"),
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .help("Request timeout in seconds")
+                        .default_value("30"),
+                ),
+        )
+        .arg(
+            Arg::new("index-url")
+                .long("index-url")
+                .env("GO_INDEX_URL")
+                .value_name("URL")
+                .help("Index endpoint, for mirrors, proxies or a local fixture server")
+                .default_value(DEFAULT_INDEX_URL),
+        )
         .arg(
             Arg::new("start-date")
                 .long("start-date")
@@ -648,6 +943,20 @@ fn build_cli() -> Command {
                 .help("Records per batch")
                 .default_value("2000"),
         )
+        .arg(
+            Arg::new("window-requests")
+                .long("window-requests")
+                .value_name("N")
+                .help("Max concurrent requests per day when a heavy day is split into windows")
+                .default_value("4"),
+        )
+        .arg(
+            Arg::new("max-pages")
+                .long("max-pages")
+                .value_name("N")
+                .help("Pages fetched within one window before it is reported as truncated")
+                .default_value("1000"),
+        )
         .arg(
             Arg::new("timeout")
                 .long("timeout")
@@ -655,6 +964,20 @@ fn build_cli() -> Command {
                 .help("Request timeout in seconds")
                 .default_value("30"),
         )
+        .arg(
+            Arg::new("rate-limit")
+                .long("rate-limit")
+                .value_name("N")
+                .help("Max requests per second over all days, 0 for no limit")
+                .default_value("25"),
+        )
+        .arg(
+            Arg::new("burst")
+                .long("burst")
+                .value_name("N")
+                .help("Requests allowed at once above the rate after a quiet spell")
+                .default_value("50"),
+        )
         .arg(
             Arg::new("dry-run")
                 .long("dry-run")
@@ -664,9 +987,22 @@ fn build_cli() -> Command {
         .arg(
             Arg::new("resume")
                 .long("resume")
-                .help("Resume from existing partial data")
+                .help("Resume from the checkpoints in the state directory (default)")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("fresh")
+                .long("fresh")
+                .help("Discard the checkpoints in the state directory and fetch every day again")
+                .conflicts_with("resume")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("state-dir")
+                .long("state-dir")
+                .value_name("DIR")
+                .help("Directory for day files and checkpoints [default: <output>.state]"),
+        )
         .arg(
             Arg::new("verbose")
                 .long("verbose")
@@ -680,13 +1016,185 @@ fn build_cli() -> Command {
                 .help("Skip post-processing step")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("group-format")
+                .long("group-format")
+                .value_name("FORMAT")
+                .value_parser(["json", "ndjson"])
+                .help("Format of the grouped output: one JSON array, or one object per line")
+                .default_value("json"),
+        )
+        .arg(
+            Arg::new("group-by")
+                .long("group-by")
+                .value_name("KEY")
+                .value_parser(["module", "repo"])
+                .help("Group by module path, or by inferred repository listing its modules")
+                .default_value("module"),
+        )
+        .arg(
+            Arg::new("emit")
+                .long("emit")
+                .value_name("RECORDS")
+                .value_parser(["grouped", "enricher"])
+                .help("Grouped versions, or one download/source/version record per module for go-enricher")
+                .default_value("grouped"),
+        )
+        .arg(
+            Arg::new("proxy-url")
+                .long("proxy-url")
+                .env("GOPROXY")
+                .value_name("URL")
+                .help("Module proxy the enricher download URLs point at, the first proxy of a GOPROXY list")
+                .default_value(proxy::DEFAULT_PROXY_URL),
+        )
+        .arg(
+            Arg::new("last-seen-after")
+                .long("last-seen-after")
+                .value_name("DATE")
+                .help("Group only modules with a record on or after this date (YYYY-MM-DD)"),
+        )
+        .arg(
+            Arg::new("include-host")
+                .long("include-host")
+                .value_name("GLOB")
+                .action(clap::ArgAction::Append)
+                .help("Group only modules on matching hosts, e.g. github.com or *.sr.ht"),
+        )
+        .arg(
+            Arg::new("exclude-host")
+                .long("exclude-host")
+                .value_name("GLOB")
+                .action(clap::ArgAction::Append)
+                .help("Skip modules on matching hosts"),
+        )
+        .arg(
+            Arg::new("path-regex")
+                .long("path-regex")
+                .value_name("REGEX")
+                .help("Group only module paths matching this regex"),
+        )
+        .arg(
+            Arg::new("exclude-path-regex")
+                .long("exclude-path-regex")
+                .value_name("REGEX")
+                .help("Skip module paths matching this regex"),
+        )
+        .arg(
+            Arg::new("major-versions")
+                .long("major-versions")
+                .value_name("POLICY")
+                .value_parser(["all", "latest", "unsuffixed"])
+                .help("Major version suffixes (/v2, .v3) to keep: all, only the highest, or none")
+                .default_value("all"),
+        )
+        .arg(
+            Arg::new("exclude-internal")
+                .long("exclude-internal")
+                .help("Skip modules whose path ends in internal, example(s), test(s), testdata or vendor")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .value_name("FILE")
+                .help("Write a JSON report of the run: days, records, errors by kind, bytes, per-day latency"),
+        )
+        .arg(
+            Arg::new("max-failed-days")
+                .long("max-failed-days")
+                .value_name("N")
+                .help("Exit with status 3 when more than N days fail or are truncated"),
+        );
+
+    #[cfg(feature = "sqlite")]
+    let cli = cli
+        .arg(
+            Arg::new("sqlite")
+                .long("sqlite")
+                .value_name("FILE")
+                .help("Also upsert the fetched records into this SQLite database"),
+        )
+        .subcommand(sqlite::query_command());
+
+    cli
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let matches = build_cli().get_matches();
 
+    if let Some(("serve-fixture", sub)) = matches.subcommand() {
+        let dir = sub.get_one::<String>("dir").unwrap();
+        let listen = sub.get_one::<String>("listen").unwrap();
+        let throttle = sub.get_one::<String>("throttle").unwrap().parse()?;
+        return fixture::serve(Path::new(dir), listen, throttle).await;
+    }
+
+    if let Some(("follow", sub)) = matches.subcommand() {
+        return follow::follow(sub).await;
+    }
+
+    if let Some(("proxy", sub)) = matches.subcommand() {
+        let input = sub.get_one::<String>("input").unwrap();
+        let output = match sub.get_one::<String>("output") {
+            Some(output) => output.clone(),
+            None => format!(
+                "{}.proxy.jsonl",
+                input.trim_end_matches(".jsonl").trim_end_matches(".json")
+            ),
+        };
+        let timeout = Duration::from_secs(sub.get_one::<String>("timeout").unwrap().parse()?);
+        let concurrent = sub.get_one::<String>("concurrent").unwrap().parse()?;
+        let remote = Remote::new(
+            create_http_client(timeout).await,
+            rate_limiter(sub, concurrent)?,
+            proxy::parse_proxy_list(sub.get_one::<String>("proxy-url").unwrap())?,
+            sub.get_one::<String>("retries").unwrap().parse()?,
+        );
+        return proxy::enrich(remote, Path::new(input), Path::new(&output), concurrent).await;
+    }
+
+    if let Some(("sumdb", sub)) = matches.subcommand() {
+        let input = sub.get_one::<String>("input").unwrap();
+        let output = match sub.get_one::<String>("output") {
+            Some(output) => output.clone(),
+            None => format!(
+                "{}.sumdb.jsonl",
+                input.trim_end_matches(".jsonl").trim_end_matches(".json")
+            ),
+        };
+        let (key, url) = sumdb::parse_gosumdb(
+            sub.get_one::<String>("sumdb-key").unwrap(),
+            sub.get_one::<String>("sumdb-url").map(String::as_str),
+        )?;
+        let timeout = Duration::from_secs(sub.get_one::<String>("timeout").unwrap().parse()?);
+        let concurrent = sub.get_one::<String>("concurrent").unwrap().parse()?;
+        let remote = Remote::new(
+            create_http_client(timeout).await,
+            rate_limiter(sub, concurrent)?,
+            url,
+            sub.get_one::<String>("retries").unwrap().parse()?,
+        );
+        return sumdb::verify(
+            remote,
+            &key,
+            Path::new(input),
+            Path::new(&output),
+            concurrent,
+        )
+        .await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(("query", sub)) = matches.subcommand() {
+        return sqlite::query(sub);
+    }
+
     let mut config = Config::default();
+    let index_url = matches.get_one::<String>("index-url").unwrap();
+    config.index_url =
+        Url::parse(index_url).with_context(|| format!("Invalid index URL: {}", index_url))?;
 
     // Parse dates
     let start_date_str = matches.get_one::<String>("start-date").unwrap_or_else(|| {
@@ -708,15 +1216,70 @@ async fn main() -> Result<()> {
 
     // Parse other options
     config.output_file = matches.get_one::<String>("output").unwrap().clone();
+    config.state_dir = match matches.get_one::<String>("state-dir") {
+        Some(dir) => dir.clone(),
+        None => format!("{}.state", config.output_file.trim_end_matches(".jsonl")),
+    };
     config.max_concurrent_days = matches.get_one::<String>("concurrent").unwrap().parse()?;
     config.max_retries = matches.get_one::<String>("retries").unwrap().parse()?;
     config.batch_size = matches.get_one::<String>("batch-size").unwrap().parse()?;
+    config.max_window_requests = matches
+        .get_one::<String>("window-requests")
+        .unwrap()
+        .parse()?;
+    config.max_window_pages = matches.get_one::<String>("max-pages").unwrap().parse()?;
     config.request_timeout =
         Duration::from_secs(matches.get_one::<String>("timeout").unwrap().parse()?);
+    config.rate_limit = matches.get_one::<String>("rate-limit").unwrap().parse()?;
+    config.burst = matches.get_one::<String>("burst").unwrap().parse()?;
+    // Every window of every day draws from the same budget
+    config.limiter = rate_limiter(
+        &matches,
+        config.max_concurrent_days * config.max_window_requests.max(1),
+    )?;
     config.dry_run = matches.get_flag("dry-run");
-    config.resume_mode = matches.get_flag("resume");
+    config.resume_mode = !matches.get_flag("fresh");
     config.verbose = matches.get_flag("verbose");
     config.process_output = !matches.get_flag("no-process");
+    config.group_format = GroupFormat::parse(matches.get_one::<String>("group-format").unwrap())?;
+    config.group_by = GroupBy::parse(matches.get_one::<String>("group-by").unwrap())?;
+    config.report = matches.get_one::<String>("report").cloned();
+    config.max_failed_days = matches
+        .get_one::<String>("max-failed-days")
+        .map(|n| n.parse())
+        .transpose()?;
+    #[cfg(feature = "sqlite")]
+    {
+        config.sqlite = matches.get_one::<String>("sqlite").cloned();
+    }
+    if matches.get_one::<String>("emit").unwrap() == "enricher" {
+        config.records = Records::Enricher {
+            proxy: proxy::parse_proxy_list(matches.get_one::<String>("proxy-url").unwrap())?,
+        };
+    }
+
+    let strings = |id: &str| -> Vec<String> {
+        matches
+            .get_many::<String>(id)
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default()
+    };
+    config.filter = Filter::default()
+        .with_hosts(&strings("include-host"), &strings("exclude-host"))?
+        .with_path_regex(
+            matches.get_one::<String>("path-regex").map(String::as_str),
+            matches
+                .get_one::<String>("exclude-path-regex")
+                .map(String::as_str),
+        )?
+        .with_non_public_excluded(matches.get_flag("exclude-internal"));
+    config.filter.major = MajorPolicy::parse(matches.get_one::<String>("major-versions").unwrap())?;
+    if let Some(date) = matches.get_one::<String>("last-seen-after") {
+        config.filter.last_seen_after = Some(
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .with_context(|| format!("Invalid last seen date: {}", date))?,
+        );
+    }
 
     // Print configuration
 
@@ -727,23 +1290,85 @@ async fn main() -> Result<()> {
     println!("│ {:<28} : {:>27} │", "Start date", start_date_fmt);
     println!("│ {:<28} : {:>27} │", "End date", end_date_fmt);
     println!("│ {:<28} : {:>27} │", "Output file", config.output_file);
+    println!("│ {:<28} : {:>27} │", "State directory", config.state_dir);
     println!(
         "│ {:<28} : {:>27} │",
         "Max concurrent", config.max_concurrent_days
     );
     println!("│ {:<28} : {:>27} │", "Max retries", config.max_retries);
     println!("│ {:<28} : {:>27} │", "Batch size", config.batch_size);
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Window requests", config.max_window_requests
+    );
+    println!("│ {:<28} : {:>27} │", "Max pages", config.max_window_pages);
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Index URL",
+        config.index_url.as_str()
+    );
     println!(
         "│ {:<28} : {:>27} │",
         "Timeout",
         format!("{}s", config.request_timeout.as_secs())
     );
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Rate limit",
+        if config.rate_limit > 0.0 {
+            format!("{}/s, burst {}", config.rate_limit, config.burst)
+        } else {
+            "off".to_string()
+        }
+    );
     println!("│ {:<28} : {:>27} │", "Dry run", config.dry_run);
     println!("│ {:<28} : {:>27} │", "Resume mode", config.resume_mode);
     println!(
         "│ {:<28} : {:>27} │",
         "Process output", config.process_output
     );
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Group format",
+        matches.get_one::<String>("group-format").unwrap()
+    );
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Group by",
+        matches.get_one::<String>("group-by").unwrap()
+    );
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Emit",
+        matches.get_one::<String>("emit").unwrap()
+    );
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Group filters",
+        if config.filter.is_active() {
+            "on"
+        } else {
+            "off"
+        }
+    );
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Report",
+        config.report.as_deref().unwrap_or("off")
+    );
+    println!(
+        "│ {:<28} : {:>27} │",
+        "Max failed days",
+        config
+            .max_failed_days
+            .map_or("unlimited".to_string(), |n| n.to_string())
+    );
+    #[cfg(feature = "sqlite")]
+    println!(
+        "│ {:<28} : {:>27} │",
+        "SQLite",
+        config.sqlite.as_deref().unwrap_or("off")
+    );
     println!("└────────────────────────────────────────────────────────────┘");
 
     let dates = generate_dates(config.start_date, config.end_date);
@@ -761,44 +1386,93 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
-    // Create temp directory
-    let temp_dir_handle = tempfile::tempdir()?;
-    let temp_dir = temp_dir_handle.path();
-    println!("📂 Temp directory: {}", temp_dir.display());
+    // Day files and checkpoints survive the run so it can be resumed
+    let state = StateDir::open(&config.state_dir)?;
+    if config.resume_mode {
+        if let Some(cursor) = state.last_complete_cursor()? {
+            println!("⏩ Resuming, last complete cursor: {}", cursor);
+        }
+    } else {
+        state.reset()?;
+    }
+    println!("📂 State directory: {}", state.path().display());
 
     let stats = Statistics::new(dates.len());
+    let token = CancellationToken::new();
+    spawn_signal_handler(token.clone());
+
+    // Process all days
+    let days = process_days_parallel(&config, dates, &state, &stats, &token).await?;
+
+    // Combine daily files, on shutdown this flushes whatever was checkpointed
+    #[cfg_attr(not(feature = "sqlite"), allow(unused_variables))]
+    let (total_lines, appended_from) = combine_daily_files(&state, &config.output_file).await?;
+
+    #[cfg(feature = "sqlite")]
+    if let Some(db) = &config.sqlite {
+        sqlite::load(
+            db,
+            &config.output_file,
+            appended_from,
+            &sqlite::Run {
+                started_at: Utc::now() - stats.start_time.elapsed(),
+                index_url: config.index_url.as_str(),
+                start_date: config.start_date,
+                end_date: config.end_date,
+                errors: stats.total_errors.load(Ordering::Relaxed),
+                truncated_days: stats.truncated_days.load(Ordering::Relaxed),
+                interrupted: token.is_cancelled(),
+            },
+        )?;
+    }
 
-    // Use a closure to ensure cleanup happens
-    let result = async {
-        // Process all days
-        process_days_parallel(&config, dates.clone(), &temp_dir, &stats).await?;
+    let report = Report::new(&config, &stats, days, total_lines, token.is_cancelled());
+    if let Some(path) = &config.report {
+        report.write(path)?;
+    }
 
-        // Combine daily files
-        let total_lines =
-            combine_daily_files(&dates, &temp_dir, &config.output_file, config.resume_mode).await?;
+    if token.is_cancelled() {
+        stats.print_final(true);
+        println!(
+            "💾 Progress saved to {}, rerun to continue",
+            state.path().display()
+        );
+        std::process::exit(EXIT_INTERRUPTED);
+    }
 
-        // Post-process if requested
-        if config.process_output {
-            process_output_file(&config.output_file).await?;
-        }
+    // Post-process if requested
+    if config.process_output {
+        process_output_file(
+            &config.output_file,
+            config.group_format,
+            config.group_by,
+            &config.records,
+            &config.filter,
+        )
+        .await?;
+    }
 
-        // Print final statistics
-        stats.print_final();
+    // Print final statistics
+    stats.print_final(false);
 
-        if let Ok(metadata) = std::fs::metadata(&config.output_file) {
-            println!(
-                "📊 Final output: {} ({:.2} MB, {} lines)",
-                config.output_file,
-                metadata.len() as f64 / 1_048_576.0,
-                total_lines
+    if let Ok(metadata) = std::fs::metadata(&config.output_file) {
+        println!(
+            "📊 Final output: {} ({:.2} MB, {} new lines)",
+            config.output_file,
+            metadata.len() as f64 / 1_048_576.0,
+            total_lines
+        );
+    }
+
+    if let Some(max) = config.max_failed_days {
+        if report.days.failed > max {
+            eprintln!(
+                "❌ {} days failed, more than --max-failed-days {}",
+                report.days.failed, max
             );
+            std::process::exit(EXIT_FAILED_DAYS);
         }
+    }
 
-        Ok::<(), anyhow::Error>(())
-    }.await;
-
-    // Cleanup happens automatically when temp_dir_handle is dropped
-    drop(temp_dir_handle);
-
-    result
-}
\ No newline at end of file
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// What the next request should do after a page was accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// A full page with progress, request the next one
+    More,
+    /// The window is exhausted, or the index has nothing newer
+    Done,
+    /// A full page of records already seen: more than `limit` records share
+    /// the cursor timestamp and `since`-only pagination cannot move past them
+    Stalled,
+}
+
+#[derive(Debug)]
+pub struct Batch {
+    /// New records, in index order, ready to be written
+    pub lines: Vec<String>,
+    /// Lines that were not valid index records
+    pub invalid: Vec<String>,
+    /// Records already written by a previous page, dropped
+    pub duplicates: usize,
+    pub outcome: Outcome,
+}
+
+/// Pages through `[since, until)` of the index.
+///
+/// The index treats `since` as inclusive, so the next page starts at the
+/// last timestamp seen and repeats every record sharing it. Those records
+/// are remembered by `path@version` and dropped when they come back.
+#[derive(Debug, Clone)]
+pub struct Paginator {
+    /// `Timestamp` exactly as the index sent it, used for the next `since`
+    cursor: String,
+    cursor_time: DateTime<Utc>,
+    /// `path@version` of records at `cursor_time` already written
+    seen: HashSet<String>,
+    until: DateTime<Utc>,
+}
+
+fn parse_time(timestamp: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(timestamp)
+        .map_err(|e| anyhow!("Invalid timestamp {}: {}", timestamp, e))?
+        .with_timezone(&Utc))
+}
+
+impl Paginator {
+    pub fn new(cursor: &str, seen: Vec<String>, until: DateTime<Utc>) -> Result<Self> {
+        Ok(Self {
+            cursor: cursor.to_string(),
+            cursor_time: parse_time(cursor)?,
+            seen: seen.into_iter().collect(),
+            until,
+        })
+    }
+
+    /// Start a window at `since` with nothing seen yet
+    pub fn starting_at(since: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        Self {
+            cursor: since.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            cursor_time: since,
+            seen: HashSet::new(),
+            until,
+        }
+    }
+
+    /// The same position, ending the window early at `until`
+    pub fn with_until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = until;
+        self
+    }
+
+    pub fn cursor(&self) -> &str {
+        &self.cursor
+    }
+
+    pub fn cursor_time(&self) -> DateTime<Utc> {
+        self.cursor_time
+    }
+
+    pub fn until(&self) -> DateTime<Utc> {
+        self.until
+    }
+
+    /// Records already written at the cursor, persisted with checkpoints
+    pub fn seen(&self) -> Vec<String> {
+        let mut seen: Vec<_> = self.seen.iter().cloned().collect();
+        seen.sort();
+        seen
+    }
+
+    /// Take one page of the index response, `limit` being the requested page size
+    pub fn accept(&mut self, body: &str, limit: usize) -> Batch {
+        let mut batch = Batch {
+            lines: Vec::new(),
+            invalid: Vec::new(),
+            duplicates: 0,
+            outcome: Outcome::Done,
+        };
+        let mut received = 0;
+        let mut past_window = false;
+
+        for line in body.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            received += 1;
+
+            let record = serde_json::from_str::<Value>(line).ok().and_then(|json| {
+                let field = |name: &str| json.get(name)?.as_str().map(String::from);
+                let timestamp = field("Timestamp")?;
+                let time = parse_time(&timestamp).ok()?;
+                Some((timestamp, time, field("Path")?, field("Version")?))
+            });
+            let Some((timestamp, time, path, version)) = record else {
+                batch.invalid.push(line.to_string());
+                continue;
+            };
+
+            // The index ignores `until`, pages run on into the next window
+            if time >= self.until {
+                past_window = true;
+                break;
+            }
+
+            // Already written by a previous page
+            let key = format!("{}@{}", path, version);
+            if time < self.cursor_time || (time == self.cursor_time && self.seen.contains(&key)) {
+                batch.duplicates += 1;
+                continue;
+            }
+
+            if time > self.cursor_time {
+                self.cursor = timestamp;
+                self.cursor_time = time;
+                self.seen.clear();
+            }
+            self.seen.insert(key);
+            batch.lines.push(line.to_string());
+        }
+
+        batch.outcome = if past_window || received < limit {
+            Outcome::Done
+        } else if batch.lines.is_empty() {
+            Outcome::Stalled
+        } else {
+            Outcome::More
+        };
+        batch
+    }
+}
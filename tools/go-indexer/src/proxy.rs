@@ -0,0 +1,314 @@
+//! The module proxy protocol, as served by `proxy.golang.org`
+
+use crate::annotate::{annotate, read_modules};
+use crate::remote::Remote;
+use crate::semver;
+use anyhow::{anyhow, Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+pub const DEFAULT_PROXY_URL: &str = "https://proxy.golang.org";
+
+/// Pick the first proxy out of a `GOPROXY` style list such as
+/// `https://goproxy.io,direct`, `direct` and `off` not being proxies
+pub fn parse_proxy_list(value: &str) -> Result<Url> {
+    let proxy = value
+        .split([',', '|'])
+        .map(str::trim)
+        .find(|entry| !entry.is_empty() && *entry != "direct" && *entry != "off")
+        .ok_or_else(|| anyhow!("No proxy in GOPROXY list: {}", value))?;
+    Url::parse(proxy).with_context(|| format!("Invalid proxy URL: {}", proxy))
+}
+
+/// Case-encode a module path or version the way the proxy expects, an
+/// uppercase letter becoming `!` and its lowercase, so paths differing only
+/// in case stay apart on case-insensitive file systems. `None` if it holds
+/// a `!` already, which no valid module path or version does.
+pub fn escape(value: &str) -> Option<String> {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '!' => return None,
+            c if c.is_ascii_uppercase() => {
+                escaped.push('!');
+                escaped.push(c.to_ascii_lowercase());
+            }
+            c => escaped.push(c),
+        }
+    }
+    Some(escaped)
+}
+
+/// `<proxy>/<module>/@v/<version>.zip`, the source archive of one version
+pub fn zip_url(proxy: &Url, path: &str, version: &str) -> Option<String> {
+    Some(format!(
+        "{}/{}/@v/{}.zip",
+        proxy.as_str().trim_end_matches('/'),
+        escape(path)?,
+        escape(version)?
+    ))
+}
+
+/// Where the proxy got a version from, the `Origin` of a `.info` response
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Origin {
+    #[serde(rename(deserialize = "VCS"), default)]
+    vcs: Option<String>,
+    #[serde(rename(deserialize = "URL"), default)]
+    url: Option<String>,
+    #[serde(rename(deserialize = "Subdir"), default)]
+    subdir: Option<String>,
+    #[serde(rename(deserialize = "Ref"), rename(serialize = "ref"), default)]
+    reference: Option<String>,
+    #[serde(rename(deserialize = "Hash"), default)]
+    hash: Option<String>,
+}
+
+/// A `.info` or `@latest` response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Info {
+    version: String,
+    time: Option<String>,
+    origin: Option<Origin>,
+}
+
+/// A `retract` directive, a single version having `low == high`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Retraction {
+    low: String,
+    high: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rationale: Option<String>,
+}
+
+impl Retraction {
+    fn contains(&self, version: &str) -> bool {
+        semver::compare(&self.low, version).is_le() && semver::compare(version, &self.high).is_le()
+    }
+}
+
+/// `v1.0.0` or `[v1.0.0, v1.9.9]`, either possibly quoted
+fn parse_retraction(line: &str, comment: Option<&str>) -> Option<Retraction> {
+    let unquote = |v: &str| v.trim().trim_matches('"').to_string();
+    let (low, high) = match line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+        Some(range) => {
+            let (low, high) = range.split_once(',')?;
+            (unquote(low), unquote(high))
+        }
+        None => (unquote(line), unquote(line)),
+    };
+    if low.is_empty() || high.is_empty() {
+        return None;
+    }
+    Some(Retraction {
+        low,
+        high,
+        rationale: comment
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(String::from),
+    })
+}
+
+/// The `retract` directives of a go.mod, single line and block form
+pub fn parse_retractions(go_mod: &str) -> Vec<Retraction> {
+    let mut retractions = Vec::new();
+    let mut in_block = false;
+    for raw in go_mod.lines() {
+        let (line, comment) = match raw.split_once("//") {
+            Some((line, comment)) => (line.trim(), Some(comment)),
+            None => (raw.trim(), None),
+        };
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                retractions.extend(parse_retraction(line, comment));
+            }
+            continue;
+        }
+        let Some(rest) = line
+            .strip_prefix("retract")
+            .filter(|rest| rest.starts_with([' ', '\t', '(', '[']))
+        else {
+            continue;
+        };
+        match rest.trim() {
+            "(" => in_block = true,
+            rest => retractions.extend(parse_retraction(rest, comment)),
+        }
+    }
+    retractions
+}
+
+/// What the proxy knows about one module
+#[derive(Debug, Serialize)]
+struct ModuleInfo {
+    /// What `go get module@latest` resolves to
+    latest: Option<String>,
+    /// Every tagged version, oldest first
+    versions: Vec<String>,
+    /// The version the rest describes: the one asked for, else `latest`
+    version: Option<String>,
+    time: Option<String>,
+    origin: Option<Origin>,
+    retractions: Vec<Retraction>,
+    retracted: bool,
+}
+
+/// What a looked up module gains
+#[derive(Debug, Serialize)]
+struct Annotation {
+    proxy: ModuleInfo,
+}
+
+/// Client of the module proxy protocol
+struct ProxyClient {
+    remote: Remote,
+}
+
+impl ProxyClient {
+    /// `<proxy>/<module>/<endpoint>`, `None` when the proxy does not have it
+    async fn get(&self, path: &str, endpoint: &str) -> Result<Option<String>> {
+        let module = escape(path).ok_or_else(|| anyhow!("Invalid module path: {}", path))?;
+        self.remote.get(&format!("{}/{}", module, endpoint)).await
+    }
+
+    async fn version_file(&self, path: &str, version: &str, ext: &str) -> Result<Option<String>> {
+        let version =
+            escape(version).ok_or_else(|| anyhow!("Invalid version of {}: {}", path, version))?;
+        self.get(path, &format!("@v/{}.{}", version, ext)).await
+    }
+
+    async fn info(&self, path: &str, version: &str) -> Result<Option<Info>> {
+        self.version_file(path, version, "info")
+            .await?
+            .map(|body| {
+                serde_json::from_str(&body)
+                    .with_context(|| format!("Invalid .info of {}@{}", path, version))
+            })
+            .transpose()
+    }
+
+    async fn lookup(&self, path: &str, wanted: Option<String>) -> Result<ModuleInfo> {
+        let mut versions: Vec<String> = self
+            .get(path, "@v/list")
+            .await?
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        semver::sort(&mut versions);
+        versions.dedup();
+
+        // The newest tag, which is also where the go command reads
+        // retractions from
+        let listed = semver::latest(&versions);
+        let tagged = listed.latest_release.or(listed.latest_prerelease);
+
+        // File proxies need not serve `@latest`, the go command then falls
+        // back to the list
+        let latest = match self.get(path, "@latest").await? {
+            Some(body) => Some(
+                serde_json::from_str::<Info>(&body)
+                    .with_context(|| format!("Invalid @latest of {}", path))?,
+            ),
+            None => match &tagged {
+                Some(version) => self.info(path, version).await?,
+                None => None,
+            },
+        };
+
+        let version = wanted.or_else(|| latest.as_ref().map(|info| info.version.clone()));
+        let info = match (&version, &latest) {
+            (Some(version), Some(latest)) if *version == latest.version => Some(latest.clone()),
+            (Some(version), _) => self.info(path, version).await?,
+            (None, _) => None,
+        };
+
+        let retractions = match tagged.or_else(|| latest.as_ref().map(|l| l.version.clone())) {
+            Some(newest) => self
+                .version_file(path, &newest, "mod")
+                .await?
+                .map(|go_mod| parse_retractions(&go_mod))
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let retracted = version
+            .as_deref()
+            .is_some_and(|v| retractions.iter().any(|r| r.contains(v)));
+
+        let (time, origin) = info.map_or((None, None), |info| (info.time, info.origin));
+        Ok(ModuleInfo {
+            latest: latest.map(|info| info.version),
+            versions,
+            version,
+            time,
+            origin,
+            retractions,
+            retracted,
+        })
+    }
+}
+
+/// Look every module of `input` up on the proxy, writing each with a
+/// `proxy` object (or a `proxy_error`) to `output` as one JSON object per line
+pub async fn enrich(remote: Remote, input: &Path, output: &Path, concurrent: usize) -> Result<()> {
+    let modules = read_modules(input)?;
+    println!(
+        "🔎 Looking up {} modules on {} ({} concurrent)",
+        modules.count,
+        remote.base(),
+        concurrent
+    );
+
+    let progress = ProgressBar::new(modules.count as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("🔎 Proxy [{bar:40.cyan/blue}] {pos}/{len} modules ({percent}%) {msg}")
+            .unwrap(),
+    );
+    let client = Arc::new(ProxyClient {
+        remote: remote.with_progress(progress.clone()),
+    });
+    let (mut found, mut missing, mut retracted) = (0, 0, 0);
+    let failed = annotate(
+        modules,
+        output,
+        "proxy_error",
+        concurrent,
+        &progress,
+        move |path, wanted| {
+            let client = client.clone();
+            async move {
+                let proxy = client.lookup(&path, wanted).await?;
+                Ok(Annotation { proxy })
+            }
+        },
+        |Annotation { proxy: info }| {
+            if info.retracted {
+                retracted += 1;
+            }
+            if info.latest.is_some() || !info.versions.is_empty() {
+                found += 1;
+            } else {
+                missing += 1;
+            }
+        },
+    )
+    .await?;
+    progress.finish_and_clear();
+
+    println!("✅ Proxy lookup complete!");
+    println!("📄 Output file: {}", output.display());
+    println!("📊 Found: {}, not on proxy: {}", found, missing);
+    println!("🚫 Retracted versions: {}", retracted);
+    if failed > 0 {
+        println!("❌ Failed lookups: {}", failed);
+    }
+    Ok(())
+}
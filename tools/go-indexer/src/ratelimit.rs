@@ -0,0 +1,287 @@
+//! One request budget shared by every task of a run: a token bucket for the
+//! request rate, and a limit on requests in flight that halves on each 429
+//! and grows back as requests succeed
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::time::sleep;
+
+/// Longest `Retry-After` honoured, a server asking for more is retried sooner
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// Backoff before retry `attempt` (from 1): doubling from 2 s up to 60 s,
+/// with the upper half randomised so that concurrent retries spread out
+pub fn backoff(attempt: usize) -> Duration {
+    let delay = Duration::from_secs(2)
+        .saturating_mul(1 << attempt.saturating_sub(1).min(5))
+        .min(Duration::from_secs(60));
+    delay / 2 + delay.mul_f64(fastrand::f64() / 2.0)
+}
+
+/// `Retry-After` as seconds or as an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => (DateTime::parse_from_rfc2822(value)
+            .ok()?
+            .with_timezone(&Utc)
+            - Utc::now())
+        .to_std()
+        .unwrap_or_default(),
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    refilled: Instant,
+    /// No request starts before this, set by `Retry-After`
+    paused_until: Instant,
+    in_flight: usize,
+    /// Requests allowed in flight, between 1 and `max_in_flight`
+    limit: f64,
+    /// 429 responses so far
+    throttled: usize,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Requests per second, 0 for no limit
+    rate: f64,
+    burst: f64,
+    max_in_flight: usize,
+    state: Mutex<State>,
+    released: Notify,
+}
+
+/// A request in flight, making room for the next one when dropped
+pub struct Permit<'a>(&'a RateLimiter);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().in_flight -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64, burst: usize, max_in_flight: usize) -> Self {
+        let burst = burst.max(1) as f64;
+        let max_in_flight = max_in_flight.max(1);
+        Self {
+            rate,
+            burst,
+            max_in_flight,
+            state: Mutex::new(State {
+                tokens: burst,
+                refilled: Instant::now(),
+                paused_until: Instant::now(),
+                in_flight: 0,
+                limit: max_in_flight as f64,
+                throttled: 0,
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    /// Wait for room in flight, then for a token
+    pub async fn acquire(&self) -> Permit<'_> {
+        loop {
+            let released = self.released.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if (state.in_flight as f64) < state.limit.floor() {
+                    state.in_flight += 1;
+                    break;
+                }
+            }
+            released.await;
+        }
+        let permit = Permit(self);
+
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                if state.paused_until > now {
+                    state.paused_until - now
+                } else if self.rate <= 0.0 {
+                    return permit;
+                } else {
+                    let elapsed = now.duration_since(state.refilled).as_secs_f64();
+                    state.tokens = (state.tokens + elapsed * self.rate).min(self.burst);
+                    state.refilled = now;
+                    if state.tokens >= 1.0 {
+                        state.tokens -= 1.0;
+                        return permit;
+                    }
+                    Duration::from_secs_f64((1.0 - state.tokens) / self.rate)
+                }
+            };
+            sleep(wait).await;
+        }
+    }
+
+    /// Grow the limit by about one request per round trip
+    pub fn succeeded(&self) {
+        let mut state = self.state.lock().unwrap();
+        let grown = (state.limit + 1.0 / state.limit).min(self.max_in_flight as f64);
+        if grown.floor() > state.limit.floor() {
+            self.released.notify_one();
+        }
+        state.limit = grown;
+    }
+
+    /// Halve the limit on a 429
+    pub fn rate_limited(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = (state.limit / 2.0).max(1.0);
+        state.throttled += 1;
+    }
+
+    /// Hold every request back until `delay` has passed
+    pub fn pause(&self, delay: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.paused_until = state.paused_until.max(Instant::now() + delay);
+    }
+
+    /// Requests currently allowed in flight
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit as usize
+    }
+
+    pub fn throttled(&self) -> usize {
+        self.state.lock().unwrap().throttled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use tokio::time::timeout;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn burst_is_spent_before_the_rate_applies() {
+        let limiter = RateLimiter::new(20.0, 3, 8);
+        let start = Instant::now();
+        for _ in 0..3 {
+            drop(limiter.acquire().await);
+        }
+        assert!(start.elapsed() < Duration::from_millis(40));
+
+        let start = Instant::now();
+        for _ in 0..2 {
+            drop(limiter.acquire().await);
+        }
+        // Two tokens at 20 per second
+        assert!(start.elapsed() >= Duration::from_millis(90));
+
+        let unlimited = RateLimiter::new(0.0, 1, 8);
+        let start = Instant::now();
+        for _ in 0..100 {
+            drop(unlimited.acquire().await);
+        }
+        assert!(start.elapsed() < Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn limit_halves_on_429_and_grows_back() {
+        let limiter = RateLimiter::new(0.0, 1, 8);
+        assert_eq!(limiter.limit(), 8);
+        for expected in [4, 2, 1, 1] {
+            limiter.rate_limited();
+            assert_eq!(limiter.limit(), expected);
+        }
+        assert_eq!(limiter.throttled(), 4);
+
+        limiter.succeeded();
+        assert_eq!(limiter.limit(), 2);
+        for _ in 0..3 {
+            limiter.succeeded();
+        }
+        assert_eq!(limiter.limit(), 3);
+        for _ in 0..100 {
+            limiter.succeeded();
+        }
+        assert_eq!(limiter.limit(), 8);
+    }
+
+    #[tokio::test]
+    async fn requests_in_flight_are_held_at_the_limit() {
+        let limiter = RateLimiter::new(0.0, 1, 2);
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+        assert!(timeout(Duration::from_millis(50), limiter.acquire())
+            .await
+            .is_err());
+        drop(first);
+        assert!(timeout(Duration::from_millis(50), limiter.acquire())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn pause_holds_back_acquire() {
+        let limiter = RateLimiter::new(0.0, 1, 4);
+        limiter.pause(Duration::from_millis(100));
+        // A shorter pause does not cut the longer one short
+        limiter.pause(Duration::from_millis(10));
+        let start = Instant::now();
+        drop(limiter.acquire().await);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+
+        let start = Instant::now();
+        drop(limiter.acquire().await);
+        assert!(start.elapsed() < Duration::from_millis(40));
+    }
+
+    #[test]
+    fn retry_after_takes_seconds_or_a_date() {
+        assert_eq!(retry_after(&headers("120")), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(&headers("0")), Some(Duration::ZERO));
+        assert_eq!(retry_after(&headers("86400")), Some(MAX_RETRY_AFTER));
+
+        let at = Utc::now() + chrono::Duration::seconds(90);
+        let delay = retry_after(&headers(
+            &at.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        ))
+        .unwrap();
+        assert!(delay > Duration::from_secs(80) && delay <= Duration::from_secs(90));
+
+        let at = Utc::now() + chrono::Duration::hours(1);
+        let delay = retry_after(&headers(
+            &at.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        ));
+        assert_eq!(delay, Some(MAX_RETRY_AFTER));
+
+        assert_eq!(
+            retry_after(&headers("Sun, 06 Nov 1994 08:49:37 GMT")),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn backoff_doubles_within_a_randomised_upper_half() {
+        for (attempt, max) in [(0, 2), (1, 2), (2, 4), (3, 8), (5, 32), (6, 60), (40, 60)] {
+            let max = Duration::from_secs(max);
+            for _ in 0..50 {
+                let delay = backoff(attempt);
+                assert!(delay >= max / 2 && delay <= max, "{} {:?}", attempt, delay);
+            }
+        }
+    }
+}
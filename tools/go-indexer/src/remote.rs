@@ -0,0 +1,84 @@
+use crate::ratelimit::RateLimiter;
+use crate::{fetch_bytes_with_retry, HttpStatus};
+use anyhow::{anyhow, Context, Result};
+use indicatif::ProgressBar;
+use reqwest::{Client, StatusCode, Url};
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+/// A server laid out as static files, such as a module proxy or a checksum
+/// database, over HTTP or as a `file://` directory for offline use
+pub struct Remote {
+    client: Client,
+    limiter: Arc<RateLimiter>,
+    base: Url,
+    max_retries: usize,
+    pub progress: ProgressBar,
+}
+
+impl Remote {
+    pub fn new(client: Client, limiter: Arc<RateLimiter>, base: Url, max_retries: usize) -> Self {
+        Self {
+            client,
+            limiter,
+            base,
+            max_retries,
+            progress: ProgressBar::hidden(),
+        }
+    }
+
+    /// Report retries on `progress`
+    pub fn with_progress(mut self, progress: ProgressBar) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn base(&self) -> &Url {
+        &self.base
+    }
+
+    /// `<base>/<path>`, `None` when the server does not have it
+    pub async fn get_bytes(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        if self.base.scheme() == "file" {
+            let dir = self
+                .base
+                .to_file_path()
+                .map_err(|_| anyhow!("Invalid file URL: {}", self.base))?;
+            let file = dir.join(path);
+            return match tokio::fs::read(&file).await {
+                Ok(body) => Ok(Some(body)),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e).with_context(|| format!("Failed to read {}", file.display())),
+            };
+        }
+
+        let url = format!("{}/{}", self.base.as_str().trim_end_matches('/'), path);
+        match fetch_bytes_with_retry(
+            &self.client,
+            &self.limiter,
+            &url,
+            self.max_retries,
+            &self.progress,
+        )
+        .await
+        {
+            Ok(body) => Ok(Some(body)),
+            Err(e)
+                if e.downcast_ref::<HttpStatus>()
+                    .is_some_and(|HttpStatus(status)| {
+                        *status == StatusCode::NOT_FOUND || *status == StatusCode::GONE
+                    }) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Option<String>> {
+        Ok(self
+            .get_bytes(path)
+            .await?
+            .map(|body| String::from_utf8_lossy(&body).into_owned()))
+    }
+}
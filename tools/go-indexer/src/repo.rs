@@ -0,0 +1,93 @@
+use crate::filter::major_suffix;
+
+/// Hosts where a repository is always `host/owner/repo`, anything below it
+/// being a package or a nested module of that repository
+const FORGES: &[&str] = &[
+    "github.com",
+    "gitlab.com",
+    "bitbucket.org",
+    "codeberg.org",
+    "gitee.com",
+    "gitea.com",
+    "git.sr.ht",
+    // golang.org/x/<repo>, mirrored from go.googlesource.com
+    "golang.org",
+];
+
+/// Infer the repository a module path lives in, e.g. `github.com/foo/bar`
+/// for `github.com/foo/bar/v2` and `github.com/foo/bar/tools`. Paths on
+/// unknown hosts only lose their major version suffix, telling a vanity
+/// import's repository apart needs a `?go-get=1` lookup.
+pub fn repo_root(path: &str) -> String {
+    let elements: Vec<&str> = path.split('/').collect();
+
+    if FORGES.contains(&elements[0]) && elements.len() >= 3 {
+        return elements[..3].join("/");
+    }
+
+    // gopkg.in/pkg.v3 lives at github.com/go-pkg/pkg and gopkg.in/user/pkg.v3
+    // at github.com/user/pkg, packages may follow
+    if elements[0] == "gopkg.in" {
+        if let Some(at) = elements.iter().position(|e| e.contains(".v")) {
+            let path = elements[..=at].join("/");
+            let (name, _) = major_suffix(&path);
+            return match name.split('/').collect::<Vec<_>>()[..] {
+                [_, pkg] => format!("github.com/go-{}/{}", pkg, pkg),
+                [_, user, pkg] => format!("github.com/{}/{}", user, pkg),
+                _ => name.to_string(),
+            };
+        }
+    }
+
+    major_suffix(path).0.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forge_paths_keep_owner_and_repo() {
+        assert_eq!(repo_root("github.com/foo/bar"), "github.com/foo/bar");
+        assert_eq!(
+            repo_root("github.com/foo/bar/cmd/baz"),
+            "github.com/foo/bar"
+        );
+        assert_eq!(repo_root("github.com/foo/bar/v2"), "github.com/foo/bar");
+        assert_eq!(
+            repo_root("gitlab.com/foo/bar/tools/v3"),
+            "gitlab.com/foo/bar"
+        );
+        assert_eq!(repo_root("golang.org/x/tools/gopls"), "golang.org/x/tools");
+    }
+
+    #[test]
+    fn short_forge_paths_are_kept_whole() {
+        assert_eq!(repo_root("github.com"), "github.com");
+        assert_eq!(repo_root("github.com/foo"), "github.com/foo");
+        assert_eq!(repo_root("gopkg.in"), "gopkg.in");
+    }
+
+    #[test]
+    fn gopkg_in_paths_point_at_github() {
+        assert_eq!(repo_root("gopkg.in/yaml.v3"), "github.com/go-yaml/yaml");
+        assert_eq!(repo_root("gopkg.in/check.v1"), "github.com/go-check/check");
+        assert_eq!(
+            repo_root("gopkg.in/src-d/go-git.v4"),
+            "github.com/src-d/go-git"
+        );
+        assert_eq!(
+            repo_root("gopkg.in/src-d/go-git.v4/plumbing"),
+            "github.com/src-d/go-git"
+        );
+        assert_eq!(repo_root("gopkg.in/yaml"), "gopkg.in/yaml");
+    }
+
+    #[test]
+    fn vanity_paths_only_lose_their_major_version() {
+        assert_eq!(repo_root("go.uber.org/zap"), "go.uber.org/zap");
+        assert_eq!(repo_root("k8s.io/client-go/v12"), "k8s.io/client-go");
+        assert_eq!(repo_root("example.com/a/b/c"), "example.com/a/b/c");
+        assert_eq!(repo_root("example.com/v1"), "example.com/v1");
+    }
+}
@@ -0,0 +1,199 @@
+//! Machine-readable summary of a fetch run, written with `--report`
+
+use crate::{Config, HttpStatus, RetriesExhausted, Statistics};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::Ordering;
+
+/// Exit status when more days failed than `--max-failed-days` allows
+pub const EXIT_FAILED_DAYS: i32 = 3;
+
+/// A day that still had records after `--max-pages` pages in one window
+#[derive(Debug, Clone, Serialize)]
+pub struct Truncated {
+    pub cursor: String,
+    pub pages: usize,
+}
+
+impl fmt::Display for Truncated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Truncated at {}: records left after {} pages, rerun to continue",
+            self.cursor, self.pages
+        )
+    }
+}
+
+impl std::error::Error for Truncated {}
+
+/// Error kind counted under `errors` in the report
+pub fn error_kind(e: &anyhow::Error) -> &'static str {
+    if let Some(e) = e.downcast_ref::<RetriesExhausted>() {
+        e.kind
+    } else if e.is::<HttpStatus>() {
+        "http_status"
+    } else {
+        "other"
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayResult {
+    Complete,
+    /// Complete in an earlier run, not fetched again
+    Skipped,
+    Failed,
+    Truncated,
+    Interrupted,
+}
+
+/// How one day went, with the time it took
+#[derive(Debug, Serialize)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub result: DayResult,
+    pub records: usize,
+    pub seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip)]
+    pub truncated: Option<Truncated>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DayCounts {
+    /// Days in the requested range
+    pub total: usize,
+    /// Days handed out before the run ended, skipped ones included
+    pub attempted: usize,
+    pub completed: usize,
+    pub skipped: usize,
+    /// Failed days, truncated ones included
+    pub failed: usize,
+    pub truncated: usize,
+    pub interrupted: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TruncationEvent {
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub truncated: Truncated,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// `complete`, `partial` when days failed within `--max-failed-days`,
+    /// `failed` beyond it, or `interrupted`
+    pub status: &'static str,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_secs: f64,
+    pub index_url: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub output_file: String,
+    pub days: DayCounts,
+    pub failed_dates: Vec<NaiveDate>,
+    pub records: usize,
+    /// New lines appended to the output file
+    pub output_lines: usize,
+    /// Records repeated across pages and dropped
+    pub duplicates_dropped: usize,
+    pub pages: usize,
+    /// Response bodies as received, after gzip decoding
+    pub body_bytes: u64,
+    /// 429 responses, each halving the requests allowed in flight
+    pub rate_limited: usize,
+    /// Requests allowed in flight at the end of the run
+    pub in_flight_limit: usize,
+    pub errors: BTreeMap<&'static str, usize>,
+    pub truncations: Vec<TruncationEvent>,
+    pub per_day: Vec<Day>,
+}
+
+impl Report {
+    pub fn new(
+        config: &Config,
+        stats: &Statistics,
+        mut per_day: Vec<Day>,
+        output_lines: usize,
+        interrupted: bool,
+    ) -> Self {
+        per_day.sort_by_key(|day| day.date);
+
+        let mut days = DayCounts {
+            total: stats.total_days,
+            attempted: per_day.len(),
+            ..DayCounts::default()
+        };
+        let mut failed_dates = Vec::new();
+        let mut truncations = Vec::new();
+        for day in &per_day {
+            match day.result {
+                DayResult::Complete => days.completed += 1,
+                DayResult::Skipped => days.skipped += 1,
+                DayResult::Interrupted => days.interrupted += 1,
+                DayResult::Failed | DayResult::Truncated => {
+                    days.failed += 1;
+                    failed_dates.push(day.date);
+                }
+            }
+            if let Some(truncated) = &day.truncated {
+                days.truncated += 1;
+                truncations.push(TruncationEvent {
+                    date: day.date,
+                    truncated: truncated.clone(),
+                });
+            }
+        }
+
+        let status = if interrupted {
+            "interrupted"
+        } else if config.max_failed_days.is_some_and(|max| days.failed > max) {
+            "failed"
+        } else if days.failed > 0 {
+            "partial"
+        } else {
+            "complete"
+        };
+        let elapsed = stats.start_time.elapsed();
+        let finished_at = Utc::now();
+
+        Self {
+            status,
+            started_at: finished_at - elapsed,
+            finished_at,
+            duration_secs: elapsed.as_secs_f64(),
+            index_url: config.index_url.to_string(),
+            start_date: config.start_date,
+            end_date: config.end_date,
+            output_file: config.output_file.clone(),
+            days,
+            failed_dates,
+            records: stats.total_records.load(Ordering::Relaxed),
+            output_lines,
+            duplicates_dropped: stats.duplicates.load(Ordering::Relaxed),
+            pages: stats.pages.load(Ordering::Relaxed),
+            body_bytes: stats.body_bytes.load(Ordering::Relaxed),
+            rate_limited: config.limiter.throttled(),
+            in_flight_limit: config.limiter.limit(),
+            errors: stats.error_kinds.lock().unwrap().clone(),
+            truncations,
+            per_day,
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write report {}", path))?;
+        println!("📝 Report written to {}", path);
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+//! Go's flavour of semantic versioning, as in `golang.org/x/mod/semver` and
+//! `golang.org/x/mod/module`
+
+use serde::Serialize;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Version<'a> {
+    major: &'a str,
+    minor: &'a str,
+    patch: &'a str,
+    /// Without the leading `-`, empty for releases
+    prerelease: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Release,
+    Prerelease,
+    /// `v0.0.0-20240101120000-abcdef123456` and the like, an untagged commit
+    Pseudo,
+}
+
+/// Newest version of each kind, `None` when the module has none
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Latest {
+    pub latest_release: Option<String>,
+    pub latest_prerelease: Option<String>,
+    pub latest_pseudo: Option<String>,
+}
+
+fn number(s: &str) -> Option<(&str, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, rest) = s.split_at(end);
+    // No leading zeros, except for zero itself
+    if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+        return None;
+    }
+    Some((digits, rest))
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn is_numeric(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn parse(v: &str) -> Option<Version<'_>> {
+    // Module versions are always canonical, the `v1` and `v1.2` shorthands
+    // of `golang.org/x/mod/semver` are not accepted
+    let (major, rest) = number(v.strip_prefix('v')?)?;
+    let (minor, rest) = number(rest.strip_prefix('.')?)?;
+    let (patch, rest) = number(rest.strip_prefix('.')?)?;
+
+    // Build metadata such as `+incompatible` is validated but plays no part
+    // in ordering
+    let (rest, build) = match rest.split_once('+') {
+        Some((rest, build)) => (rest, Some(build)),
+        None => (rest, None),
+    };
+    let prerelease = match rest {
+        "" => "",
+        _ => rest.strip_prefix('-')?,
+    };
+    if rest.starts_with('-')
+        && !prerelease
+            .split('.')
+            .all(|id| is_identifier(id) && !(is_numeric(id) && id.len() > 1 && id.starts_with('0')))
+    {
+        return None;
+    }
+    if build.is_some_and(|b| !b.split('.').all(is_identifier)) {
+        return None;
+    }
+
+    Some(Version {
+        major,
+        minor,
+        patch,
+        prerelease,
+    })
+}
+
+fn compare_int(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Identifiers compare numerically when both are numbers, numbers sort
+/// before words, and a shorter prerelease sorts first when it is a prefix
+fn compare_prerelease(a: &str, b: &str) -> Ordering {
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        (false, false) => {}
+    }
+    let mut left = a.split('.');
+    let mut right = b.split('.');
+    loop {
+        let ordering = match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (is_numeric(x), is_numeric(y)) {
+                (true, true) => compare_int(x, y),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Semver precedence, build metadata such as `+incompatible` is ignored.
+/// Invalid versions sort before every valid one.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    match (parse(a), parse(b)) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(x), Some(y)) => compare_int(x.major, y.major)
+            .then_with(|| compare_int(x.minor, y.minor))
+            .then_with(|| compare_int(x.patch, y.patch))
+            .then_with(|| compare_prerelease(x.prerelease, y.prerelease)),
+    }
+}
+
+/// Whether the prerelease is the tail of a pseudo-version:
+/// `yyyymmddhhmmss-rev` after `vX.0.0-`, or `0.yyyymmddhhmmss-rev` after
+/// `vX.Y.Z-` and `vX.Y.Z-pre.`
+fn is_pseudo(version: &Version) -> bool {
+    let Some((rest, revision)) = version.prerelease.rsplit_once('-') else {
+        return false;
+    };
+    if revision.is_empty() || !revision.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    let Some(base) = rest.len().checked_sub(14).map(|at| rest.split_at(at)) else {
+        return false;
+    };
+    let (base, timestamp) = base;
+    if !is_numeric(timestamp) {
+        return false;
+    }
+    match base {
+        "" => version.minor == "0" && version.patch == "0",
+        base => base == "0." || base.ends_with(".0."),
+    }
+}
+
+pub fn kind(v: &str) -> Option<Kind> {
+    let version = parse(v)?;
+    Some(if is_pseudo(&version) {
+        Kind::Pseudo
+    } else if version.prerelease.is_empty() {
+        Kind::Release
+    } else {
+        Kind::Prerelease
+    })
+}
+
+/// Oldest first, ties broken by the string so the order is stable
+pub fn sort(versions: &mut [String]) {
+    versions.sort_by(|a, b| compare(a, b).then_with(|| a.cmp(b)));
+}
+
+/// Pick the newest release, prerelease and pseudo-version out of `versions`
+pub fn latest(versions: &[String]) -> Latest {
+    let mut latest = Latest::default();
+    for version in versions {
+        let slot = match kind(version) {
+            Some(Kind::Release) => &mut latest.latest_release,
+            Some(Kind::Prerelease) => &mut latest.latest_prerelease,
+            Some(Kind::Pseudo) => &mut latest.latest_pseudo,
+            None => continue,
+        };
+        let newer = match slot.as_deref() {
+            Some(current) => {
+                compare(version, current).then_with(|| version.as_str().cmp(current))
+                    == Ordering::Greater
+            }
+            None => true,
+        };
+        if newer {
+            *slot = Some(version.clone());
+        }
+    }
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn non_canonical_versions_are_rejected() {
+        for v in [
+            "v1",
+            "v1.2",
+            "1.2.3",
+            "v01.2.3",
+            "v1.02.3",
+            "v1.2.03",
+            "v1.2.3-",
+            "v1.2.3-01",
+            "v1.2.3-a..b",
+            "v1.2.3+",
+            "v1.2.3+a_b",
+            "v1.2.3.4",
+        ] {
+            assert_eq!(kind(v), None, "{}", v);
+        }
+        assert_eq!(kind("v0.0.0"), Some(Kind::Release));
+        assert_eq!(kind("v1.2.3-rc.0"), Some(Kind::Prerelease));
+    }
+
+    #[test]
+    fn build_metadata_is_ignored_in_ordering() {
+        assert_eq!(kind("v2.0.0+incompatible"), Some(Kind::Release));
+        assert_eq!(kind("v1.2.3+build.5"), Some(Kind::Release));
+        assert_eq!(compare("v2.0.0+incompatible", "v2.0.0"), Ordering::Equal);
+        assert_eq!(compare("v2.1.0+incompatible", "v2.0.0"), Ordering::Greater);
+        assert_eq!(compare("v1.0.0-rc.1+meta", "v1.0.0-rc.1"), Ordering::Equal);
+    }
+
+    #[test]
+    fn prerelease_identifiers_compare_numerically_before_words() {
+        let mut list = versions(&[
+            "v1.0.0",
+            "v1.0.0-rc.1",
+            "v1.0.0-beta.11",
+            "v1.0.0-alpha.beta",
+            "v1.0.0-beta",
+            "v1.0.0-alpha",
+            "v1.0.0-beta.2",
+            "v1.0.0-alpha.1",
+            "v1.0.0-1",
+        ]);
+        sort(&mut list);
+        assert_eq!(
+            list,
+            [
+                "v1.0.0-1",
+                "v1.0.0-alpha",
+                "v1.0.0-alpha.1",
+                "v1.0.0-alpha.beta",
+                "v1.0.0-beta",
+                "v1.0.0-beta.2",
+                "v1.0.0-beta.11",
+                "v1.0.0-rc.1",
+                "v1.0.0",
+            ]
+        );
+        assert_eq!(compare("v1.10.0", "v1.9.0"), Ordering::Greater);
+        assert_eq!(compare("not-a-version", "v0.0.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn all_pseudo_version_forms_are_recognised() {
+        // No earlier tag
+        assert_eq!(
+            kind("v0.0.0-20240101120000-abcdef123456"),
+            Some(Kind::Pseudo)
+        );
+        assert_eq!(
+            kind("v2.0.0-20240101120000-abcdef123456"),
+            Some(Kind::Pseudo)
+        );
+        // After a prerelease tag
+        assert_eq!(
+            kind("v1.2.3-pre.0.20240101120000-abcdef123456"),
+            Some(Kind::Pseudo)
+        );
+        // After a release tag, with the patch bumped
+        assert_eq!(
+            kind("v1.2.4-0.20240101120000-abcdef123456"),
+            Some(Kind::Pseudo)
+        );
+
+        assert_eq!(
+            kind("v1.2.3-20240101120000-abcdef123456"),
+            Some(Kind::Prerelease)
+        );
+        assert_eq!(
+            kind("v1.2.4-1.20240101120000-abcdef123456"),
+            Some(Kind::Prerelease)
+        );
+        assert_eq!(kind("v1.2.4-0.2024010112-abcdef"), Some(Kind::Prerelease));
+    }
+
+    #[test]
+    fn latest_picks_the_newest_of_each_kind() {
+        let found = latest(&versions(&[
+            "v1.9.0",
+            "v1.10.0",
+            "v2.0.0+incompatible",
+            "v1.11.0-rc.1",
+            "v1.11.0-rc.2",
+            "v0.0.0-20230101000000-aaaaaaaaaaaa",
+            "v1.10.1-0.20240101000000-bbbbbbbbbbbb",
+            "garbage",
+        ]));
+        assert_eq!(
+            found,
+            Latest {
+                latest_release: Some("v2.0.0+incompatible".to_string()),
+                latest_prerelease: Some("v1.11.0-rc.2".to_string()),
+                latest_pseudo: Some("v1.10.1-0.20240101000000-bbbbbbbbbbbb".to_string()),
+            }
+        );
+        assert_eq!(latest(&[]), Latest::default());
+    }
+}
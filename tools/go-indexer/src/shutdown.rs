@@ -0,0 +1,51 @@
+use std::fmt;
+use tokio_util::sync::CancellationToken;
+
+/// Exit status after a Ctrl-C or SIGTERM, distinct from plain failures
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Returned by work that stopped because shutdown was requested
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Interrupted by shutdown request")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Cancel `token` on the first Ctrl-C/SIGTERM, exit immediately on the second
+pub fn spawn_signal_handler(token: CancellationToken) {
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        eprintln!("\n🛑 Shutdown requested, saving checkpoints (repeat to force quit)...");
+        token.cancel();
+        wait_for_signal().await;
+        eprintln!("🛑 Forced exit");
+        std::process::exit(EXIT_INTERRUPTED);
+    });
+}
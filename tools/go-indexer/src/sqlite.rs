@@ -0,0 +1,321 @@
+//! SQLite storage of the index: modules, their versions and the fetch runs
+//! that loaded them, upserted so incremental runs add to one database
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, SecondsFormat, TimeDelta, Utc};
+use clap::{Arg, ArgMatches, Command};
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS fetch_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    index_url TEXT NOT NULL,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    records INTEGER NOT NULL DEFAULT 0,
+    errors INTEGER NOT NULL DEFAULT 0,
+    truncated_days INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS modules (
+    path TEXT PRIMARY KEY,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS modules_first_seen ON modules (first_seen);
+CREATE INDEX IF NOT EXISTS modules_last_seen ON modules (last_seen);
+CREATE TABLE IF NOT EXISTS versions (
+    path TEXT NOT NULL REFERENCES modules (path),
+    version TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    run_id INTEGER REFERENCES fetch_runs (id),
+    PRIMARY KEY (path, version)
+);
+CREATE INDEX IF NOT EXISTS versions_timestamp ON versions (timestamp);
+";
+
+/// A fetch run as recorded in `fetch_runs`
+pub struct Run<'a> {
+    pub started_at: DateTime<Utc>,
+    pub index_url: &'a str,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub errors: usize,
+    pub truncated_days: usize,
+    pub interrupted: bool,
+}
+
+fn open(path: &str) -> Result<Connection> {
+    let db = Connection::open(path).with_context(|| format!("Failed to open database {}", path))?;
+    db.pragma_update(None, "journal_mode", "WAL")?;
+    db.execute_batch(SCHEMA)?;
+    Ok(db)
+}
+
+/// Fixed width timestamps, so they compare correctly as text
+fn normalize(timestamp: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| {
+        t.with_timezone(&Utc)
+            .to_rfc3339_opts(SecondsFormat::Micros, true)
+    })
+}
+
+/// Upsert the records of `input` from byte `from` on, the part of the
+/// output this run appended, and record the run. Returns the records loaded.
+pub fn load(db_path: &str, input: &str, from: u64, run: &Run) -> Result<usize> {
+    println!("🗄️  Loading new records into {}...", db_path);
+    let mut db = open(db_path)?;
+    let tx = db.transaction()?;
+
+    tx.execute(
+        "INSERT INTO fetch_runs (started_at, index_url, start_date, end_date, status)
+         VALUES (?1, ?2, ?3, ?4, 'loading')",
+        params![
+            run.started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            run.index_url,
+            run.start_date.to_string(),
+            run.end_date.to_string(),
+        ],
+    )?;
+    let run_id = tx.last_insert_rowid();
+
+    let mut file = File::open(input)?;
+    file.seek(SeekFrom::Start(from))?;
+    let (mut loaded, mut skipped) = (0, 0);
+    {
+        let mut upsert_module = tx.prepare(
+            "INSERT INTO modules (path, first_seen, last_seen) VALUES (?1, ?2, ?2)
+             ON CONFLICT (path) DO UPDATE SET
+                 first_seen = min(first_seen, excluded.first_seen),
+                 last_seen = max(last_seen, excluded.last_seen)",
+        )?;
+        // A version keeps the time it was first seen at
+        let mut upsert_version = tx.prepare(
+            "INSERT INTO versions (path, version, timestamp, run_id) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (path, version) DO UPDATE SET
+                 timestamp = min(timestamp, excluded.timestamp)",
+        )?;
+
+        for line in BufReader::with_capacity(256 * 1024, file).lines() {
+            let line = line?;
+            let record: Value = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(_) => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let field = |name: &str| record.get(name).and_then(Value::as_str);
+            let (Some(path), Some(version), Some(timestamp)) = (
+                field("Path"),
+                field("Version"),
+                field("Timestamp").and_then(normalize),
+            ) else {
+                skipped += 1;
+                continue;
+            };
+            upsert_module.execute(params![path, timestamp])?;
+            upsert_version.execute(params![path, version, timestamp, run_id])?;
+            loaded += 1;
+        }
+    }
+
+    tx.execute(
+        "UPDATE fetch_runs SET finished_at = ?1, records = ?2, errors = ?3,
+             truncated_days = ?4, status = ?5
+         WHERE id = ?6",
+        params![
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            loaded,
+            run.errors,
+            run.truncated_days,
+            if run.interrupted {
+                "interrupted"
+            } else {
+                "complete"
+            },
+            run_id,
+        ],
+    )?;
+    tx.commit()?;
+
+    let (modules, versions): (i64, i64) = db.query_row(
+        "SELECT (SELECT count(*) FROM modules), (SELECT count(*) FROM versions)",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    println!(
+        "🗄️  Loaded {} records as run {} ({} modules, {} versions in total)",
+        loaded, run_id, modules, versions
+    );
+    if skipped > 0 {
+        println!(
+            "⚠️  Skipped records without Path, Version or Timestamp: {}",
+            skipped
+        );
+    }
+    Ok(loaded)
+}
+
+pub fn query_command() -> Command {
+    let date =
+        |id: &'static str, help: &'static str| Arg::new(id).long(id).value_name("DATE").help(help);
+    Command::new("query")
+        .about("Common reports over a database written with --sqlite")
+        .subcommand_required(true)
+        .arg(
+            Arg::new("db")
+                .long("db")
+                .value_name("FILE")
+                .required(true)
+                .help("SQLite database"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .value_parser(["tsv", "ndjson"])
+                .default_value("tsv")
+                .global(true)
+                .help("Tab separated with a header row, or one JSON object per row"),
+        )
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .value_name("N")
+                .global(true)
+                .help("Print at most N rows"),
+        )
+        .subcommand(
+            Command::new("new-modules")
+                .about("Modules first seen on or after a date")
+                .arg(date("since", "YYYY-MM-DD [default: a week ago]")),
+        )
+        .subcommand(
+            Command::new("versions")
+                .about("Versions of a module, or the version count of every module")
+                .arg(
+                    Arg::new("module")
+                        .long("module")
+                        .value_name("PATH")
+                        .help("Module path"),
+                ),
+        )
+        .subcommand(
+            Command::new("stale")
+                .about("Modules without a new version since a date")
+                .arg(date("before", "YYYY-MM-DD").required(true)),
+        )
+        .subcommand(Command::new("runs").about("Fetch runs, newest first"))
+}
+
+fn parse_date(value: &str) -> Result<String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.to_string())
+        .with_context(|| format!("Invalid date: {}", value))
+}
+
+pub fn query(matches: &ArgMatches) -> Result<()> {
+    let db_path = matches.get_one::<String>("db").unwrap();
+    if !std::path::Path::new(db_path).exists() {
+        return Err(anyhow!("No database at {}", db_path));
+    }
+    let db = open(db_path)?;
+
+    let (report, sub) = matches.subcommand().unwrap();
+    let (sql, args): (&str, Vec<String>) = match report {
+        "new-modules" => {
+            let since = match sub.get_one::<String>("since") {
+                Some(since) => parse_date(since)?,
+                None => (Utc::now().date_naive() - TimeDelta::days(7)).to_string(),
+            };
+            (
+                "SELECT m.path, m.first_seen, count(v.version) AS versions
+                 FROM modules m JOIN versions v ON v.path = m.path
+                 WHERE m.first_seen >= ?1
+                 GROUP BY m.path ORDER BY m.first_seen, m.path",
+                vec![since],
+            )
+        }
+        "versions" => match sub.get_one::<String>("module") {
+            Some(module) => (
+                "SELECT version, timestamp FROM versions WHERE path = ?1 ORDER BY timestamp",
+                vec![module.clone()],
+            ),
+            None => (
+                "SELECT path, count(*) AS versions, max(timestamp) AS latest
+                 FROM versions GROUP BY path ORDER BY versions DESC, path",
+                vec![],
+            ),
+        },
+        "stale" => (
+            "SELECT path, last_seen FROM modules WHERE last_seen < ?1 ORDER BY last_seen, path",
+            vec![parse_date(sub.get_one::<String>("before").unwrap())?],
+        ),
+        "runs" => (
+            "SELECT id, started_at, finished_at, start_date, end_date, records, errors,
+                 truncated_days, status
+             FROM fetch_runs ORDER BY id DESC",
+            vec![],
+        ),
+        other => return Err(anyhow!("Unknown report: {}", other)),
+    };
+    let limit = sub
+        .get_one::<String>("limit")
+        .map(|limit| limit.parse::<usize>())
+        .transpose()?;
+    let ndjson = sub
+        .get_one::<String>("format")
+        .is_some_and(|f| f == "ndjson");
+
+    let mut statement = db.prepare(sql)?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if !ndjson {
+        println!("{}", columns.join("\t"));
+    }
+    let mut rows = statement.query(rusqlite::params_from_iter(&args))?;
+    let mut printed = 0;
+    while let Some(row) = rows.next()? {
+        if limit.is_some_and(|limit| printed >= limit) {
+            break;
+        }
+        let values = (0..columns.len())
+            .map(|i| {
+                Ok(match row.get_ref(i)? {
+                    ValueRef::Null => Value::Null,
+                    ValueRef::Integer(n) => Value::from(n),
+                    ValueRef::Real(n) => Value::from(n),
+                    ValueRef::Text(text) | ValueRef::Blob(text) => {
+                        Value::from(String::from_utf8_lossy(text).into_owned())
+                    }
+                })
+            })
+            .collect::<Result<Vec<Value>>>()?;
+        if ndjson {
+            let object: Map<String, Value> = columns.iter().cloned().zip(values).collect();
+            println!("{}", Value::Object(object));
+        } else {
+            let cells: Vec<String> = values
+                .iter()
+                .map(|value| match value {
+                    Value::String(text) => text.clone(),
+                    Value::Null => String::new(),
+                    other => other.to_string(),
+                })
+                .collect();
+            println!("{}", cells.join("\t"));
+        }
+        printed += 1;
+    }
+    Ok(())
+}
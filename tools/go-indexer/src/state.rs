@@ -0,0 +1,196 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DayStatus {
+    /// Interrupted, or the day had not ended yet when it was fetched
+    Partial,
+    Complete,
+}
+
+/// Progress of a single day, saved after every batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayCheckpoint {
+    pub status: DayStatus,
+    /// `Timestamp` of the last record written, the `since` of the next request
+    pub cursor: Option<String>,
+    /// `path@version` of records at the cursor timestamp already written
+    #[serde(default)]
+    pub seen: Vec<String>,
+    pub records: usize,
+    /// Length of the day file covered by this checkpoint
+    pub bytes: u64,
+    /// How much of the day file has been appended to the output dataset
+    #[serde(default)]
+    pub exported: u64,
+}
+
+/// Where `follow` polls the index from next
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowState {
+    pub cursor: String,
+    /// `path@version` of records at the cursor timestamp already written
+    #[serde(default)]
+    pub seen: Vec<String>,
+}
+
+/// Where the output dataset was last written to and how long it was
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportState {
+    pub output: String,
+    pub length: u64,
+}
+
+/// Persistent directory holding day files and their checkpoints
+///
+/// ```text
+/// <state>/days/day_2024_01_31.jsonl   raw index records
+/// <state>/days/day_2024_01_31.json    DayCheckpoint
+/// <state>/export.json                 ExportState
+/// <state>/follow.json                 FollowState
+/// ```
+#[derive(Debug, Clone)]
+pub struct StateDir {
+    root: PathBuf,
+}
+
+fn day_stem(date: NaiveDate) -> String {
+    format!("day_{}", date.format("%Y_%m_%d"))
+}
+
+/// Write through a temporary file so a crash never leaves half a checkpoint
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+impl StateDir {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(root.join("days"))
+            .with_context(|| format!("Failed to create state directory {}", root.display()))?;
+        Ok(Self { root })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// Forget all progress, used when not resuming
+    pub fn reset(&self) -> Result<()> {
+        fs::remove_dir_all(self.root.join("days"))?;
+        fs::create_dir_all(self.root.join("days"))?;
+        let export = self.root.join("export.json");
+        if export.exists() {
+            fs::remove_file(export)?;
+        }
+        Ok(())
+    }
+
+    pub fn day_file(&self, date: NaiveDate) -> PathBuf {
+        self.root
+            .join("days")
+            .join(format!("{}.jsonl", day_stem(date)))
+    }
+
+    fn checkpoint_file(&self, date: NaiveDate) -> PathBuf {
+        self.root
+            .join("days")
+            .join(format!("{}.json", day_stem(date)))
+    }
+
+    pub fn load_day(&self, date: NaiveDate) -> Result<Option<DayCheckpoint>> {
+        let path = self.checkpoint_file(date);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)?;
+        let checkpoint = serde_json::from_str(&contents)
+            .with_context(|| format!("Corrupt checkpoint {}", path.display()))?;
+        Ok(Some(checkpoint))
+    }
+
+    pub fn save_day(&self, date: NaiveDate, checkpoint: &DayCheckpoint) -> Result<()> {
+        write_atomic(
+            &self.checkpoint_file(date),
+            &serde_json::to_vec_pretty(checkpoint)?,
+        )
+    }
+
+    /// All days with a checkpoint, oldest first
+    pub fn days(&self) -> Result<Vec<(NaiveDate, DayCheckpoint)>> {
+        let mut days = Vec::new();
+        for entry in fs::read_dir(self.root.join("days"))? {
+            let name = entry?.file_name();
+            let Some(stem) = name
+                .to_str()
+                .and_then(|n| n.strip_suffix(".json"))
+                .and_then(|n| n.strip_prefix("day_"))
+            else {
+                continue;
+            };
+            let Ok(date) = NaiveDate::parse_from_str(stem, "%Y_%m_%d") else {
+                continue;
+            };
+            if let Some(checkpoint) = self.load_day(date)? {
+                days.push((date, checkpoint));
+            }
+        }
+        days.sort_by_key(|(date, _)| *date);
+        Ok(days)
+    }
+
+    /// Checkpoint of the newest complete day
+    pub fn last_complete(&self) -> Result<Option<DayCheckpoint>> {
+        Ok(self
+            .days()?
+            .into_iter()
+            .rev()
+            .find(|(_, c)| c.status == DayStatus::Complete)
+            .map(|(_, c)| c))
+    }
+
+    /// Cursor of the newest complete day, where an incremental run picks up
+    pub fn last_complete_cursor(&self) -> Result<Option<String>> {
+        Ok(self.last_complete()?.and_then(|c| c.cursor))
+    }
+
+    pub fn load_export(&self) -> Result<Option<ExportState>> {
+        let path = self.root.join("export.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    pub fn save_export(&self, export: &ExportState) -> Result<()> {
+        write_atomic(
+            &self.root.join("export.json"),
+            &serde_json::to_vec_pretty(export)?,
+        )
+    }
+
+    pub fn load_follow(&self) -> Result<Option<FollowState>> {
+        let path = self.root.join("follow.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)?;
+        let follow = serde_json::from_str(&contents)
+            .with_context(|| format!("Corrupt follow cursor {}", path.display()))?;
+        Ok(Some(follow))
+    }
+
+    pub fn save_follow(&self, follow: &FollowState) -> Result<()> {
+        write_atomic(
+            &self.root.join("follow.json"),
+            &serde_json::to_vec_pretty(follow)?,
+        )
+    }
+}
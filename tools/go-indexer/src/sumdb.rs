@@ -0,0 +1,416 @@
+//! Verification against the Go checksum database, as served by
+//! `sum.golang.org`: signed tree heads, `/lookup` records and inclusion
+//! proofs read from the log's tiles
+
+use crate::annotate::{annotate, read_modules};
+use crate::proxy::escape;
+use crate::remote::Remote;
+use anyhow::{anyhow, Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use openssl::base64::decode_block;
+use openssl::pkey::{Id, PKey, Public};
+use openssl::sha::Sha256;
+use openssl::sign::Verifier;
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Levels of the tree each tile spans, the height sum.golang.org serves
+const TILE_HEIGHT: u32 = 8;
+const TILE_WIDTH: u64 = 1 << TILE_HEIGHT;
+
+type Hash = [u8; 32];
+
+/// `<name>+<key hash>+<base64 of algorithm byte and public key>`
+struct VerifierKey {
+    name: String,
+    hash: [u8; 4],
+    key: PKey<Public>,
+}
+
+impl VerifierKey {
+    fn parse(vkey: &str) -> Result<Self> {
+        let mut parts = vkey.splitn(3, '+');
+        let (Some(name), Some(hash), Some(key)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(anyhow!("Malformed verifier key: {}", vkey));
+        };
+        let key = decode_block(key).with_context(|| format!("Malformed verifier key: {}", vkey))?;
+        // Algorithm 1 is Ed25519, the only one notes are signed with
+        if key.len() != 33 || key[0] != 1 {
+            return Err(anyhow!("Unsupported verifier key: {}", vkey));
+        }
+
+        let mut digest = Sha256::new();
+        digest.update(name.as_bytes());
+        digest.update(b"\n");
+        digest.update(&key);
+        let expected: [u8; 4] = digest.finish()[..4].try_into().unwrap();
+        if format!("{:08x}", u32::from_be_bytes(expected)) != hash {
+            return Err(anyhow!("Verifier key hash mismatch: {}", vkey));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            hash: expected,
+            key: PKey::public_key_from_raw_bytes(&key[1..], Id::ED25519)?,
+        })
+    }
+
+    /// The text of a signed note, once a signature by this key checks out
+    fn open<'a>(&self, note: &'a str) -> Result<&'a str> {
+        let split = note
+            .rfind("\n\n")
+            .ok_or_else(|| anyhow!("Malformed signed note"))?;
+        let (text, signatures) = (&note[..split + 1], &note[split + 2..]);
+        for line in signatures.lines() {
+            let Some((name, signature)) = line
+                .strip_prefix("\u{2014} ")
+                .and_then(|line| line.split_once(' '))
+            else {
+                continue;
+            };
+            if name != self.name {
+                continue;
+            }
+            let signature = decode_block(signature)?;
+            if signature.len() < 4 || signature[..4] != self.hash {
+                continue;
+            }
+            if Verifier::new_without_digest(&self.key)?
+                .verify_oneshot(&signature[4..], text.as_bytes())?
+            {
+                return Ok(text);
+            }
+        }
+        Err(anyhow!("No valid signature by {} on note", self.name))
+    }
+}
+
+/// A signed tree head
+#[derive(Debug, Clone, Copy)]
+struct Tree {
+    size: u64,
+    root: Hash,
+}
+
+impl Tree {
+    fn parse(text: &str) -> Result<Self> {
+        let lines: Vec<&str> = text.lines().collect();
+        let ["go.sum database tree", size, root] = lines[..] else {
+            return Err(anyhow!("Malformed tree note: {:?}", text));
+        };
+        Ok(Self {
+            size: size.parse().context("Malformed tree size")?,
+            root: decode_block(root)?
+                .try_into()
+                .map_err(|_| anyhow!("Malformed tree hash"))?,
+        })
+    }
+}
+
+fn record_hash(data: &[u8]) -> Hash {
+    let mut digest = Sha256::new();
+    digest.update(&[0]);
+    digest.update(data);
+    digest.finish()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut digest = Sha256::new();
+    digest.update(&[1]);
+    digest.update(left);
+    digest.update(right);
+    digest.finish()
+}
+
+/// `tile/8/<level>/<index>[.p/<width>]`, the index in three digit groups
+/// such as `x001/x234/067`
+fn tile_path(level: u32, index: u64, width: u64) -> String {
+    let mut digits = format!("{:03}", index % 1000);
+    let mut rest = index / 1000;
+    while rest > 0 {
+        digits = format!("x{:03}/{}", rest % 1000, digits);
+        rest /= 1000;
+    }
+    let mut path = format!("tile/{}/{}/{}", TILE_HEIGHT, level, digits);
+    if width < TILE_WIDTH {
+        path.push_str(&format!(".p/{}", width));
+    }
+    path
+}
+
+/// What the checksum database says about one version
+#[derive(Debug, Serialize)]
+struct SumdbRecord {
+    /// Position of the record in the log
+    record: u64,
+    /// Size of the signed tree it was proven against
+    tree_size: u64,
+    /// The `go.sum` lines, module zip and go.mod hashes
+    lines: Vec<String>,
+}
+
+/// What a checked module gains
+#[derive(Debug, Serialize)]
+struct Annotation {
+    sumdb_verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sumdb: Option<SumdbRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sumdb_error: Option<String>,
+}
+
+struct SumdbClient {
+    remote: Remote,
+    key: VerifierKey,
+    latest: Tree,
+    tiles: Mutex<HashMap<String, Arc<Vec<u8>>>>,
+}
+
+impl SumdbClient {
+    async fn open(remote: Remote, key: VerifierKey) -> Result<Self> {
+        let note = remote
+            .get("latest")
+            .await?
+            .ok_or_else(|| anyhow!("Checksum database has no latest tree"))?;
+        let latest = Tree::parse(key.open(&note)?)?;
+        Ok(Self {
+            remote,
+            key,
+            latest,
+            tiles: Mutex::new(HashMap::new()),
+        })
+    }
+
+    async fn tile(&self, level: u32, index: u64, width: u64) -> Result<Arc<Vec<u8>>> {
+        let path = tile_path(level, index, width);
+        if let Some(tile) = self.tiles.lock().unwrap().get(&path) {
+            return Ok(tile.clone());
+        }
+        let tile = self
+            .remote
+            .get_bytes(&path)
+            .await?
+            .ok_or_else(|| anyhow!("Missing tile {}", path))?;
+        if tile.len() as u64 != width * 32 {
+            return Err(anyhow!("Tile {} has {} bytes", path, tile.len()));
+        }
+        let tile = Arc::new(tile);
+        self.tiles.lock().unwrap().insert(path, tile.clone());
+        Ok(tile)
+    }
+
+    /// Hash of the complete subtree `index` at `level` of a tree of `size`
+    /// records, from the tile holding the hashes it is made of
+    async fn subtree_hash(&self, size: u64, level: u32, index: u64) -> Result<Hash> {
+        let tile_level = level / TILE_HEIGHT;
+        let sublevel = level % TILE_HEIGHT;
+        let stored = size >> (tile_level * TILE_HEIGHT);
+        let first = index << sublevel;
+        let tile = first / TILE_WIDTH;
+        let width = (stored - tile * TILE_WIDTH).min(TILE_WIDTH);
+        let bytes = self.tile(tile_level, tile, width).await?;
+
+        let start = ((first % TILE_WIDTH) * 32) as usize;
+        let mut hashes: Vec<Hash> = bytes[start..start + (32 << sublevel)]
+            .chunks_exact(32)
+            .map(|hash| hash.try_into().unwrap())
+            .collect();
+        while hashes.len() > 1 {
+            hashes = hashes
+                .chunks_exact(2)
+                .map(|pair| node_hash(&pair[0], &pair[1]))
+                .collect();
+        }
+        Ok(hashes[0])
+    }
+
+    /// Root of records `lo..hi`, with the record at `leaf` hashed from its
+    /// data and everything else read from tiles, so the result only matches
+    /// the signed root if the record is in the log
+    fn range_hash(
+        &self,
+        size: u64,
+        lo: u64,
+        hi: u64,
+        leaf: (u64, Hash),
+    ) -> Pin<Box<dyn Future<Output = Result<Hash>> + Send + '_>> {
+        Box::pin(async move {
+            let count = hi - lo;
+            let holds_leaf = (lo..hi).contains(&leaf.0);
+            if count == 1 && holds_leaf {
+                return Ok(leaf.1);
+            }
+            if count.is_power_of_two() && !holds_leaf {
+                let level = count.trailing_zeros();
+                return self.subtree_hash(size, level, lo >> level).await;
+            }
+            // Split at the largest power of two below the count, as RFC 6962
+            let split = 1 << (63 - (count - 1).leading_zeros());
+            let left = self.range_hash(size, lo, lo + split, leaf).await?;
+            let right = self.range_hash(size, lo + split, hi, leaf).await?;
+            Ok(node_hash(&left, &right))
+        })
+    }
+
+    /// Look `path@version` up and prove its record is in a signed tree.
+    /// `None` when the database has no record of it.
+    async fn verify(&self, path: &str, version: &str) -> Result<Option<SumdbRecord>> {
+        let target = format!(
+            "lookup/{}@{}",
+            escape(path).ok_or_else(|| anyhow!("Invalid module path: {}", path))?,
+            escape(version).ok_or_else(|| anyhow!("Invalid version: {}", version))?
+        );
+        let Some(body) = self.remote.get(&target).await? else {
+            return Ok(None);
+        };
+
+        // `<record id>\n<go.sum lines>\n<signed tree note>`
+        let (id, rest) = body
+            .split_once('\n')
+            .ok_or_else(|| anyhow!("Malformed lookup response"))?;
+        let record: u64 = id.trim().parse().context("Malformed record id")?;
+        let end = rest
+            .find("\n\n")
+            .ok_or_else(|| anyhow!("Malformed lookup response"))?;
+        let (data, note) = (&rest[..end + 1], &rest[end + 2..]);
+
+        let lines: Vec<String> = data.lines().map(String::from).collect();
+        let prefix = format!("{} {} ", path, version);
+        if !lines.first().is_some_and(|line| line.starts_with(&prefix)) {
+            return Err(anyhow!("Lookup returned a record for another version"));
+        }
+
+        // Records newer than the latest tree are proven against the tree
+        // that came with them
+        let tree = if record < self.latest.size {
+            self.latest
+        } else {
+            Tree::parse(self.key.open(note)?)?
+        };
+        if record >= tree.size {
+            return Err(anyhow!("Record {} is outside its tree", record));
+        }
+
+        let leaf = (record, record_hash(data.as_bytes()));
+        let root = self.range_hash(tree.size, 0, tree.size, leaf).await?;
+        if root != tree.root {
+            return Err(anyhow!(
+                "Inclusion proof of record {} in tree {} failed",
+                record,
+                tree.size
+            ));
+        }
+        Ok(Some(SumdbRecord {
+            record,
+            tree_size: tree.size,
+            lines,
+        }))
+    }
+}
+
+/// The verifier key and URL of a `GOSUMDB` style `<key> [<url>]`, the URL
+/// defaulting to `https://<name>`. A bare name such as `sum.golang.org`
+/// carries no key to check signatures with.
+pub fn parse_gosumdb(value: &str, url: Option<&str>) -> Result<(String, Url)> {
+    let mut fields = value.split_whitespace();
+    let key = fields
+        .next()
+        .filter(|key| key.contains('+'))
+        .ok_or_else(|| {
+            anyhow!(
+                "No verifier key in {:?}, expected <name>+<hash>+<key>",
+                value
+            )
+        })?;
+    let url = match url.or(fields.next()) {
+        Some(url) => Url::parse(url).with_context(|| format!("Invalid sumdb URL: {}", url))?,
+        None => {
+            let name = key.split('+').next().unwrap();
+            Url::parse(&format!("https://{}", name))
+                .with_context(|| format!("Invalid sumdb name: {}", name))?
+        }
+    };
+    Ok((key.to_string(), url))
+}
+
+/// Check the version each module of `input` points at against the checksum
+/// database, writing each with `sumdb_verified` to `output` as one JSON
+/// object per line
+pub async fn verify(
+    remote: Remote,
+    vkey: &str,
+    input: &Path,
+    output: &Path,
+    concurrent: usize,
+) -> Result<()> {
+    let modules = read_modules(input)?;
+    let key = VerifierKey::parse(vkey)?;
+    println!(
+        "🔏 Verifying {} modules against {} ({})",
+        modules.count,
+        remote.base(),
+        key.name
+    );
+
+    let progress = ProgressBar::new(modules.count as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("🔏 Sumdb [{bar:40.cyan/blue}] {pos}/{len} modules ({percent}%) {msg}")
+            .unwrap(),
+    );
+    let client = Arc::new(SumdbClient::open(remote.with_progress(progress.clone()), key).await?);
+    println!("🌳 Latest tree: {} records", client.latest.size);
+
+    let (mut verified, mut unknown, mut failed) = (0, 0, 0);
+    annotate(
+        modules,
+        output,
+        "sumdb_error",
+        concurrent,
+        &progress,
+        move |path, wanted| {
+            let client = client.clone();
+            async move {
+                let result = match wanted {
+                    Some(version) => client.verify(&path, &version).await,
+                    None => Err(anyhow!("No version to verify")),
+                };
+                Ok(match result {
+                    Ok(record) => Annotation {
+                        sumdb_verified: record.is_some(),
+                        sumdb: record,
+                        sumdb_error: None,
+                    },
+                    Err(e) => Annotation {
+                        sumdb_verified: false,
+                        sumdb: None,
+                        sumdb_error: Some(format!("{:#}", e)),
+                    },
+                })
+            }
+        },
+        |annotation: &Annotation| match annotation {
+            Annotation {
+                sumdb_error: Some(_),
+                ..
+            } => failed += 1,
+            Annotation { sumdb: Some(_), .. } => verified += 1,
+            Annotation { .. } => unknown += 1,
+        },
+    )
+    .await?;
+    progress.finish_and_clear();
+
+    println!("✅ Checksum database verification complete!");
+    println!("📄 Output file: {}", output.display());
+    println!("🔏 Verified: {}, not in database: {}", verified, unknown);
+    if failed > 0 {
+        println!("❌ Failed verifications: {}", failed);
+    }
+    Ok(())
+}
@@ -0,0 +1,296 @@
+use crate::paginate::{Batch, Outcome, Paginator};
+use crate::{fetch_with_retry, index_page_url, Config, Statistics};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, DurationRound, SecondsFormat, TimeDelta, Utc};
+use indicatif::ProgressBar;
+use reqwest::Client;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// A window is split once paging through the rest of it would take more
+/// than this many requests at the density of the last page
+const SPLIT_PAGES: i32 = 4;
+
+/// How much of the index a window covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Span {
+    Day,
+    Hour,
+    Minute,
+}
+
+impl Span {
+    /// What a heavy window of this span is split into
+    fn finer(self) -> Option<Span> {
+        match self {
+            Span::Day => Some(Span::Hour),
+            Span::Hour => Some(Span::Minute),
+            Span::Minute => None,
+        }
+    }
+
+    fn duration(self) -> TimeDelta {
+        match self {
+            Span::Day => TimeDelta::days(1),
+            Span::Hour => TimeDelta::hours(1),
+            Span::Minute => TimeDelta::minutes(1),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Span::Day => "daily",
+            Span::Hour => "hourly",
+            Span::Minute => "minute",
+        }
+    }
+}
+
+/// Why a window stopped
+#[derive(Debug)]
+pub enum WindowEnd {
+    Complete,
+    /// More records share one timestamp than fit in a page
+    Stalled,
+    /// Still more records after `pages` pages, the rest was not fetched
+    Truncated {
+        pages: usize,
+    },
+    Interrupted,
+    Failed(anyhow::Error),
+}
+
+/// Records in index order, with the position right after them
+#[derive(Debug)]
+pub struct Chunk {
+    pub lines: Vec<String>,
+    pub paginator: Paginator,
+}
+
+/// Whether the rest of the window needs more than `SPLIT_PAGES` pages,
+/// judging by how much time the last page covered
+fn is_heavy(page_start: DateTime<Utc>, paginator: &Paginator) -> bool {
+    let covered = paginator.cursor_time() - page_start;
+    let remaining = paginator.until() - paginator.cursor_time();
+    remaining > covered * SPLIT_PAGES
+}
+
+/// Cut the rest of the window at `span` boundaries. The first window keeps
+/// the cursor and the records already seen at it.
+fn split(paginator: Paginator, span: Span) -> Vec<Paginator> {
+    let end = paginator.until();
+    let mut windows = Vec::new();
+    let mut current = paginator;
+    loop {
+        let boundary = current
+            .cursor_time()
+            .duration_trunc(span.duration())
+            .map_or(end, |start| start + span.duration());
+        if boundary >= end {
+            windows.push(current);
+            return windows;
+        }
+        windows.push(current.with_until(boundary));
+        current = Paginator::starting_at(boundary, end);
+    }
+}
+
+/// Fetches the windows of one day, sharing its request budget
+#[derive(Clone)]
+pub struct Fetcher {
+    client: Client,
+    config: Config,
+    stats: Statistics,
+    requests: Arc<Semaphore>,
+    token: CancellationToken,
+    progress: ProgressBar,
+}
+
+impl Fetcher {
+    pub fn new(
+        client: Client,
+        config: Config,
+        stats: Statistics,
+        token: CancellationToken,
+        progress: ProgressBar,
+    ) -> Self {
+        let requests = Arc::new(Semaphore::new(config.max_window_requests.max(1)));
+        Self {
+            client,
+            config,
+            stats,
+            requests,
+            token,
+            progress,
+        }
+    }
+
+    /// Fetch and accept the next page, `None` once shutdown was requested
+    async fn next_page(&self, paginator: &mut Paginator) -> Result<Option<Batch>> {
+        let url = index_page_url(
+            &self.config.index_url,
+            paginator.cursor(),
+            &paginator
+                .until()
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            self.config.batch_size,
+        );
+
+        let mut empty_batches = 0;
+        loop {
+            // Records are only handed on once a page has fully arrived, so
+            // dropping the in-flight request loses nothing
+            let body = tokio::select! {
+                _ = self.token.cancelled() => return Ok(None),
+                body = async {
+                    let _permit = self.requests.acquire().await?;
+                    fetch_with_retry(
+                        &self.client,
+                        &self.config.limiter,
+                        &url,
+                        self.config.max_retries,
+                        &self.progress,
+                    )
+                    .await
+                } => body?,
+            };
+
+            // An empty page is retried a few times before taking it as the end
+            if body.trim().is_empty() {
+                empty_batches += 1;
+                if empty_batches < 3 {
+                    continue;
+                }
+            }
+
+            let batch = paginator.accept(&body, self.config.batch_size);
+            self.stats.add_page(body.len(), batch.duplicates);
+            for line in &batch.invalid {
+                self.stats.add_errors("invalid_line", 1);
+                if self.config.verbose {
+                    eprintln!("Invalid JSON line: {}", line);
+                }
+            }
+            return Ok(Some(batch));
+        }
+    }
+
+    /// Page through the window of `paginator`, sending its records in index
+    /// order. A heavy window is split into finer windows, up to
+    /// `--window-requests` of them fetched ahead while earlier ones are
+    /// forwarded in order.
+    pub fn run(
+        self,
+        mut paginator: Paginator,
+        span: Span,
+        chunks: UnboundedSender<Chunk>,
+    ) -> Pin<Box<dyn Future<Output = WindowEnd> + Send>> {
+        Box::pin(async move {
+            let mut pages = 0;
+            loop {
+                let page_start = paginator.cursor_time();
+                let batch = match self.next_page(&mut paginator).await {
+                    Ok(Some(batch)) => batch,
+                    Ok(None) => return WindowEnd::Interrupted,
+                    Err(e) => return WindowEnd::Failed(e),
+                };
+                pages += 1;
+
+                let chunk = Chunk {
+                    lines: batch.lines,
+                    paginator: paginator.clone(),
+                };
+                if chunks.send(chunk).is_err() {
+                    return WindowEnd::Interrupted;
+                }
+
+                match batch.outcome {
+                    Outcome::Done => return WindowEnd::Complete,
+                    Outcome::Stalled => return WindowEnd::Stalled,
+                    Outcome::More => {}
+                }
+
+                if let Some(finer) = span.finer() {
+                    if is_heavy(page_start, &paginator) {
+                        return self.run_split(paginator, finer, chunks).await;
+                    }
+                }
+
+                if pages >= self.config.max_window_pages {
+                    return WindowEnd::Truncated { pages };
+                }
+            }
+        })
+    }
+
+    async fn run_split(
+        self,
+        paginator: Paginator,
+        span: Span,
+        chunks: UnboundedSender<Chunk>,
+    ) -> WindowEnd {
+        let windows = split(paginator, span);
+        if self.config.verbose {
+            eprintln!(
+                "Splitting at {} into {} {} windows",
+                windows[0].cursor(),
+                windows.len(),
+                span.name()
+            );
+        }
+
+        // Only the window being forwarded and the next few are fetched, so
+        // memory does not grow with the number of windows
+        let lookahead = self.config.max_window_requests.max(1);
+        let mut windows = windows.into_iter();
+        let mut pending = Pending(VecDeque::with_capacity(lookahead));
+        loop {
+            while pending.0.len() < lookahead {
+                let Some(window) = windows.next() else {
+                    break;
+                };
+                let (sender, receiver) = unbounded_channel();
+                let handle = tokio::spawn(self.clone().run(window, span, sender));
+                pending.0.push_back((receiver, handle));
+            }
+            let Some((mut receiver, handle)) = pending.0.pop_front() else {
+                break;
+            };
+
+            while let Some(chunk) = receiver.recv().await {
+                if chunks.send(chunk).is_err() {
+                    return WindowEnd::Interrupted;
+                }
+            }
+            let end = handle
+                .await
+                .unwrap_or_else(|e| WindowEnd::Failed(anyhow!("Window task failed: {}", e)));
+            if !matches!(end, WindowEnd::Complete) {
+                return end;
+            }
+        }
+        WindowEnd::Complete
+    }
+}
+
+type Window = (
+    tokio::sync::mpsc::UnboundedReceiver<Chunk>,
+    JoinHandle<WindowEnd>,
+);
+
+/// Windows not forwarded yet, aborted when the split is abandoned
+struct Pending(VecDeque<Window>);
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        for (_, handle) in &self.0 {
+            handle.abort();
+        }
+    }
+}
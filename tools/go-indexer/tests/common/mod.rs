@@ -0,0 +1,54 @@
+//! `serve-fixture` harness shared by the integration tests
+
+// Each test crate uses its own part of this module
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+pub const BIN: &str = env!("CARGO_BIN_EXE_go-indexer");
+
+/// A `serve-fixture` process, killed when dropped
+pub struct Fixture {
+    child: Child,
+    pub url: String,
+}
+
+impl Fixture {
+    pub fn start(name: &str) -> Self {
+        Self::start_throttled(name, 0)
+    }
+
+    /// Every `throttle`th request is answered with a 429
+    pub fn start_throttled(name: &str, throttle: usize) -> Self {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        let mut child = Command::new(BIN)
+            .args(["serve-fixture", "--listen", "127.0.0.1:0", "--dir"])
+            .arg(&dir)
+            .args(["--throttle", &throttle.to_string()])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start serve-fixture");
+
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let url = line
+            .split_whitespace()
+            .find(|w| w.starts_with("http://"))
+            .unwrap_or_else(|| panic!("no URL in {:?}", line))
+            .to_string();
+        Self { child, url }
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
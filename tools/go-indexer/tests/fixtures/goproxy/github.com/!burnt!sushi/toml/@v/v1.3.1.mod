@@ -0,0 +1,8 @@
+module github.com/BurntSushi/toml
+
+go 1.16
+
+retract (
+	v1.3.0 // Panics on tables with quoted keys
+	[v1.1.0, v1.1.2]
+)
@@ -0,0 +1,67 @@
+//! Follows a recorded index with `--once` polls, checking that records are
+//! appended once and watched modules are reported on stdout and to `--exec`.
+
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+mod common;
+
+use common::{Fixture, BIN};
+
+fn follow(fixture: &Fixture, dir: &Path, extra: &[&str]) -> Vec<Value> {
+    let output = Command::new(BIN)
+        .args(["follow", "--once", "--index-url", &fixture.url])
+        .args(["--batch-size", "4", "--watch", "github.com/example/mod1*"])
+        .args(extra)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run go-indexer follow");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn lines(path: &Path) -> usize {
+    std::fs::read_to_string(path).unwrap().lines().count()
+}
+
+#[test]
+fn polls_resume_from_the_stored_cursor() {
+    let fixture = Fixture::start("shared-timestamps");
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("go_index.jsonl");
+
+    let events = follow(
+        &fixture,
+        tmp.path(),
+        &["--since", "2024-01-01", "--exec", "cat >> exec.jsonl"],
+    );
+    assert!(!events.is_empty());
+    assert!(events.iter().all(|e| e["path"]
+        .as_str()
+        .unwrap()
+        .starts_with("github.com/example/mod1")));
+    assert!(events
+        .iter()
+        .all(|e| e["watch"] == "github.com/example/mod1*"));
+    assert_eq!(lines(&tmp.path().join("exec.jsonl")), events.len());
+
+    let fetched = lines(&out);
+    let cursor: Value = serde_json::from_str(
+        &std::fs::read_to_string(tmp.path().join("go_index.state/follow.json")).unwrap(),
+    )
+    .unwrap();
+    assert!(cursor["cursor"].as_str().unwrap().starts_with("2024-01-02"));
+
+    // The stored cursor wins over --since, nothing is new
+    let events = follow(&fixture, tmp.path(), &["--since", "2024-01-01"]);
+    assert!(events.is_empty());
+    assert_eq!(lines(&out), fetched);
+}
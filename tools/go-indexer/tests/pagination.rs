@@ -0,0 +1,280 @@
+//! Replays recorded index pages through `serve-fixture` and checks that
+//! pagination neither drops nor repeats records.
+
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+mod common;
+
+use common::{Fixture, BIN};
+
+fn run(fixture: &Fixture, out: &Path, start: &str, end: &str, batch_size: &str) -> String {
+    run_with(fixture, out, start, end, batch_size, &[])
+}
+
+fn run_with(
+    fixture: &Fixture,
+    out: &Path,
+    start: &str,
+    end: &str,
+    batch_size: &str,
+    extra: &[&str],
+) -> String {
+    let output = Command::new(BIN)
+        .args(["--index-url", &fixture.url])
+        .args(extra)
+        .args(["--start-date", start, "--end-date", end])
+        .args(["--batch-size", batch_size, "--no-process", "--output"])
+        .arg(out)
+        .output()
+        .expect("failed to run go-indexer");
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+fn records(path: &Path) -> Vec<(String, String, String)> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            let json: Value = serde_json::from_str(l).unwrap();
+            let field = |name: &str| json[name].as_str().unwrap().to_string();
+            (field("Timestamp"), field("Path"), field("Version"))
+        })
+        .collect()
+}
+
+fn fixture_records(name: &str) -> HashSet<(String, String, String)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_dir(dir)
+        .unwrap()
+        .flat_map(|e| records(&e.unwrap().path()))
+        .collect()
+}
+
+fn state_file(out: &Path, name: &str) -> PathBuf {
+    out.with_extension("state").join("days").join(name)
+}
+
+#[test]
+fn shared_timestamps_across_pages() {
+    let fixture = Fixture::start("shared-timestamps");
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("out.jsonl");
+
+    // Groups of three records per timestamp never line up with pages of four
+    let log = run(&fixture, &out, "2024-01-01", "2024-01-03", "4");
+
+    let written = records(&out);
+    let unique: HashSet<_> = written.iter().cloned().collect();
+    assert_eq!(written.len(), unique.len(), "duplicate records:\n{}", log);
+    assert_eq!(unique, fixture_records("shared-timestamps"), "{}", log);
+
+    // Records land in the day their timestamp belongs to, midnight included
+    for (day, prefix) in [
+        ("day_2024_01_01", "2024-01-01"),
+        ("day_2024_01_02", "2024-01-02"),
+    ] {
+        let day_records = records(&state_file(&out, &format!("{}.jsonl", day)));
+        assert!(!day_records.is_empty());
+        assert!(day_records.iter().all(|(t, _, _)| t.starts_with(prefix)));
+
+        // Windows fetched concurrently are still merged in index order
+        let times: Vec<_> = day_records
+            .iter()
+            .map(|(t, _, _)| chrono::DateTime::parse_from_rfc3339(t).unwrap())
+            .collect();
+        assert!(times.windows(2).all(|w| w[0] <= w[1]), "{}", log);
+    }
+}
+
+#[test]
+fn resume_does_not_repeat_records() {
+    let fixture = Fixture::start("shared-timestamps");
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("out.jsonl");
+    run(&fixture, &out, "2024-01-01", "2024-01-02", "4");
+
+    // Pretend the run stopped inside a group of records sharing a timestamp
+    let checkpoint_path = state_file(&out, "day_2024_01_01.json");
+    let day_path = state_file(&out, "day_2024_01_01.jsonl");
+    let day = std::fs::read_to_string(&day_path).unwrap();
+    let kept: Vec<&str> = day.lines().take(5).collect();
+    let kept_records: Vec<Value> = kept
+        .iter()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    let cursor = kept_records[4]["Timestamp"].as_str().unwrap();
+    let seen: Vec<String> = kept_records
+        .iter()
+        .filter(|r| r["Timestamp"] == cursor)
+        .map(|r| {
+            format!(
+                "{}@{}",
+                r["Path"].as_str().unwrap(),
+                r["Version"].as_str().unwrap()
+            )
+        })
+        .collect();
+    let bytes: usize = kept.iter().map(|l| l.len() + 1).sum();
+    std::fs::write(
+        &checkpoint_path,
+        serde_json::json!({
+            "status": "partial",
+            "cursor": cursor,
+            "seen": seen,
+            "records": 5,
+            "bytes": bytes,
+        })
+        .to_string(),
+    )
+    .unwrap();
+    std::fs::remove_file(out.with_extension("state").join("export.json")).unwrap();
+
+    let output = Command::new(BIN)
+        .args(["--index-url", &fixture.url, "--resume", "--no-process"])
+        .args(["--start-date", "2024-01-01", "--end-date", "2024-01-02"])
+        .args(["--batch-size", "4", "--output"])
+        .arg(&out)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let resumed = records(&day_path);
+    let unique: HashSet<_> = resumed.iter().cloned().collect();
+    assert_eq!(resumed.len(), unique.len());
+    assert_eq!(resumed.len(), day.lines().count());
+}
+
+#[test]
+fn stall_is_reported() {
+    let fixture = Fixture::start("stalled");
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("out.jsonl");
+
+    // Six records share one timestamp, more than a page of four can hold
+    let log = run(&fixture, &out, "2024-01-01", "2024-01-02", "4");
+    assert!(log.contains("stalled"), "{}", log);
+
+    let checkpoint: Value = serde_json::from_str(
+        &std::fs::read_to_string(state_file(&out, "day_2024_01_01.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(checkpoint["status"], "partial");
+    assert_eq!(records(&out).len(), 4);
+}
+
+#[test]
+fn truncation_is_reported_and_resumable() {
+    let fixture = Fixture::start("dense-minute");
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("out.jsonl");
+
+    // A dozen records within one minute cannot be split any further, so
+    // three pages of two are not enough
+    let log = run_with(
+        &fixture,
+        &out,
+        "2024-01-01",
+        "2024-01-02",
+        "2",
+        &["--max-pages", "3"],
+    );
+    assert!(log.contains("Truncated at"), "{}", log);
+
+    let checkpoint: Value = serde_json::from_str(
+        &std::fs::read_to_string(state_file(&out, "day_2024_01_01.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(checkpoint["status"], "partial");
+    let expected = fixture_records("dense-minute");
+    assert!(records(&out).len() < expected.len());
+
+    // Resuming is the default
+    let log = run(&fixture, &out, "2024-01-01", "2024-01-02", "2");
+    let written = records(&out);
+    let unique: HashSet<_> = written.iter().cloned().collect();
+    assert_eq!(written.len(), unique.len(), "{}", log);
+    assert_eq!(unique, expected, "{}", log);
+}
+
+#[test]
+fn report_counts_truncations_against_max_failed_days() {
+    let fixture = Fixture::start("dense-minute");
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("out.jsonl");
+    let report = tmp.path().join("report.json");
+
+    let fetch = |max_failed_days: &str| {
+        Command::new(BIN)
+            .args(["--index-url", &fixture.url])
+            .args(["--start-date", "2024-01-01", "--end-date", "2024-01-03"])
+            .args([
+                "--batch-size",
+                "2",
+                "--max-pages",
+                "3",
+                "--no-process",
+                "--fresh",
+            ])
+            .args(["--max-failed-days", max_failed_days, "--report"])
+            .arg(&report)
+            .arg("--output")
+            .arg(&out)
+            .output()
+            .expect("failed to run go-indexer")
+    };
+
+    let output = fetch("0");
+    assert_eq!(output.status.code(), Some(3));
+    let json: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(json["status"], "failed");
+    assert_eq!(json["days"]["attempted"], 2);
+    assert_eq!(json["days"]["failed"], 1);
+    assert_eq!(json["days"]["truncated"], 1);
+    assert_eq!(json["failed_dates"][0], "2024-01-01");
+    assert_eq!(json["truncations"][0]["date"], "2024-01-01");
+    assert_eq!(json["truncations"][0]["pages"], 3);
+    assert_eq!(json["per_day"][0]["result"], "truncated");
+    assert_eq!(json["records"], records(&out).len());
+
+    let output = fetch("1");
+    assert!(output.status.success());
+    let json: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(json["status"], "partial");
+}
+
+#[test]
+fn rate_limited_pages_are_retried_after_retry_after() {
+    let fixture = Fixture::start_throttled("shared-timestamps", 2);
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("out.jsonl");
+    let report = tmp.path().join("report.json");
+
+    let log = run_with(
+        &fixture,
+        &out,
+        "2024-01-01",
+        "2024-01-03",
+        "2000",
+        &["--retries", "5", "--report", report.to_str().unwrap()],
+    );
+
+    let written = records(&out);
+    let unique: HashSet<_> = written.iter().cloned().collect();
+    assert_eq!(written.len(), unique.len(), "{}", log);
+    assert_eq!(unique, fixture_records("shared-timestamps"), "{}", log);
+
+    let json: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(json["status"], "complete", "{}", log);
+    assert!(json["rate_limited"].as_u64().unwrap() > 0);
+    assert!(json["errors"].as_object().unwrap().is_empty());
+}
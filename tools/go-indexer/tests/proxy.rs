@@ -0,0 +1,136 @@
+//! Looks grouped modules up on a `file://` GOPROXY directory and checks
+//! what `go-indexer proxy` reports for each.
+
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+const BIN: &str = env!("CARGO_BIN_EXE_go-indexer");
+
+fn lookup(input: &str) -> Vec<Value> {
+    let dir = tempfile::tempdir().unwrap();
+    let input_file = dir.path().join("grouped.jsonl");
+    let output_file = dir.path().join("proxy.jsonl");
+    std::fs::write(&input_file, input).unwrap();
+
+    let proxy = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/goproxy");
+    let output = Command::new(BIN)
+        .args([
+            "proxy",
+            "--proxy-url",
+            &format!("file://{}", proxy.display()),
+        ])
+        .arg("--input")
+        .arg(&input_file)
+        .arg("--output")
+        .arg(&output_file)
+        .output()
+        .expect("failed to run go-indexer proxy");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    std::fs::read_to_string(&output_file)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn versions_origin_and_retractions() {
+    let modules = lookup(concat!(
+        r#"{"download":"x","source":"github.com/BurntSushi/toml","version":"v1.3.0"}"#,
+        "\n",
+        r#"{"source":"example.com/pseudo","versions":["v0.0.0-20240102030405-abcdefabcdef"],"latest_release":null,"latest_prerelease":null,"latest_pseudo":"v0.0.0-20240102030405-abcdefabcdef"}"#,
+        "\n",
+        r#"{"source":"github.com/missing/mod","versions":["v1.0.0"],"latest_release":"v1.0.0"}"#,
+        "\n",
+    ));
+    assert_eq!(modules.len(), 3);
+
+    // No @latest in the directory: the newest listed tag stands in, while
+    // the record keeps describing the version it asked for
+    let toml = &modules[0]["proxy"];
+    assert_eq!(modules[0]["download"], "x");
+    assert_eq!(toml["latest"], "v1.3.1");
+    assert_eq!(toml["versions"].as_array().unwrap().len(), 3);
+    assert_eq!(toml["version"], "v1.3.0");
+    assert_eq!(toml["time"], "2023-03-20T10:00:00Z");
+    assert_eq!(toml["retracted"], true);
+    assert_eq!(toml["retractions"][0]["low"], "v1.3.0");
+    assert_eq!(
+        toml["retractions"][0]["rationale"],
+        "Panics on tables with quoted keys"
+    );
+    assert_eq!(toml["retractions"][1]["high"], "v1.1.2");
+
+    let pseudo = &modules[1]["proxy"];
+    assert_eq!(pseudo["latest"], "v0.0.0-20240102030405-abcdefabcdef");
+    assert!(pseudo["versions"].as_array().unwrap().is_empty());
+    assert_eq!(pseudo["retracted"], false);
+
+    let missing = &modules[2]["proxy"];
+    assert!(missing["latest"].is_null());
+    assert!(missing["origin"].is_null());
+}
+
+#[test]
+fn latest_origin_when_no_version_asked() {
+    let modules = lookup(concat!(
+        r#"[{"root":"github.com/BurntSushi/toml","modules":[{"source":"github.com/BurntSushi/toml","versions":[]}]}]"#,
+        "\n",
+    ));
+    assert_eq!(modules.len(), 1);
+
+    let toml = &modules[0]["proxy"];
+    assert_eq!(toml["version"], "v1.3.1");
+    assert_eq!(toml["retracted"], false);
+    assert_eq!(toml["origin"]["vcs"], "git");
+    assert_eq!(toml["origin"]["url"], "https://github.com/BurntSushi/toml");
+    assert_eq!(toml["origin"]["ref"], "refs/tags/v1.3.1");
+}
+
+#[test]
+fn items_that_are_not_objects_are_rejected() {
+    for (input, expected) in [
+        (
+            concat!(
+                r#"{"source":"github.com/BurntSushi/toml","version":"v1.3.0"}"#,
+                "\n\n",
+                r#""just a string""#,
+                "\n",
+            ),
+            "Line 3 of",
+        ),
+        (
+            r#"[{"source":"github.com/BurntSushi/toml"}, 42]"#,
+            "Item 2 of",
+        ),
+        (
+            r#"[{"root":"github.com/a/b","modules":[{"source":"github.com/a/b"},null]}]"#,
+            "Item 1 of",
+        ),
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        let input_file = dir.path().join("grouped.jsonl");
+        std::fs::write(&input_file, input).unwrap();
+
+        let output = Command::new(BIN)
+            .args(["proxy", "--proxy-url", "file:///nonexistent"])
+            .arg("--input")
+            .arg(&input_file)
+            .output()
+            .expect("failed to run go-indexer proxy");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains(expected) && stderr.contains("is not a JSON object"),
+            "{}",
+            stderr
+        );
+        assert!(!dir.path().join("grouped.proxy.jsonl").exists());
+    }
+}
@@ -0,0 +1,92 @@
+//! Loads fetched records into a SQLite database across incremental runs and
+//! reads them back through `query`.
+#![cfg(feature = "sqlite")]
+
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
+mod common;
+
+use common::{Fixture, BIN};
+
+fn query(db: &Path, args: &[&str]) -> Vec<Value> {
+    let output = Command::new(BIN)
+        .arg("query")
+        .arg("--db")
+        .arg(db)
+        .args(args)
+        .args(["--format", "ndjson"])
+        .output()
+        .expect("failed to run go-indexer query");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn incremental_runs_upsert() {
+    let fixture = Fixture::start("shared-timestamps");
+    let tmp = tempfile::tempdir().unwrap();
+    let out = tmp.path().join("out.jsonl");
+    let db = tmp.path().join("index.db");
+
+    // The second run fetches the same days again, adding nothing new
+    for _ in 0..2 {
+        let output = Command::new(BIN)
+            .args(["--index-url", &fixture.url])
+            .args(["--start-date", "2024-01-01", "--end-date", "2024-01-03"])
+            .args(["--fresh", "--no-process", "--sqlite"])
+            .arg(&db)
+            .arg("--output")
+            .arg(&out)
+            .output()
+            .expect("failed to run go-indexer");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let fetched: Vec<Value> = std::fs::read_to_string(&out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let unique: HashSet<(&str, &str)> = fetched
+        .iter()
+        .map(|r| (r["Path"].as_str().unwrap(), r["Version"].as_str().unwrap()))
+        .collect();
+    let paths: HashSet<&str> = fetched
+        .iter()
+        .map(|r| r["Path"].as_str().unwrap())
+        .collect();
+
+    let runs = query(&db, &["runs"]);
+    assert_eq!(runs.len(), 2);
+    assert!(runs.iter().all(|run| run["status"] == "complete"));
+    assert_eq!(runs[1]["records"], runs[0]["records"]);
+
+    let modules = query(&db, &["versions"]);
+    assert_eq!(modules.len(), paths.len());
+    let versions: i64 = modules
+        .iter()
+        .map(|m| m["versions"].as_i64().unwrap())
+        .sum();
+    assert_eq!(versions as usize, unique.len());
+
+    let since = query(&db, &["new-modules", "--since", "2024-01-02"]);
+    assert!(since
+        .iter()
+        .all(|m| m["first_seen"].as_str().unwrap() >= "2024-01-02"));
+    assert!(query(&db, &["stale", "--before", "2024-01-01"]).is_empty());
+    assert_eq!(query(&db, &["--limit", "1", "versions"]).len(), 1);
+}
@@ -0,0 +1,105 @@
+//! Verifies modules against a recorded checksum database: a log of 300
+//! records signed with a test key, with a lookup of record 300 coming with a
+//! newer tree of 301.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const BIN: &str = env!("CARGO_BIN_EXE_go-indexer");
+const KEY: &str = "sumdb.test+08833776+Ab3w79/9Xqssk3BLQapNImBvdzJoiFJWngS1jEFTocnf";
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sumdb")
+}
+
+fn verify(sumdb: &Path, dir: &Path, input: &str) -> (Output, Vec<Value>) {
+    let input_file = dir.join("grouped.jsonl");
+    let output_file = dir.join("sumdb.jsonl");
+    std::fs::write(&input_file, input).unwrap();
+
+    let output = Command::new(BIN)
+        .args(["sumdb", "--sumdb-key", KEY])
+        .args(["--sumdb-url", &format!("file://{}", sumdb.display())])
+        .arg("--input")
+        .arg(&input_file)
+        .arg("--output")
+        .arg(&output_file)
+        .env_remove("GOSUMDB")
+        .output()
+        .expect("failed to run go-indexer sumdb");
+    let modules = std::fs::read_to_string(&output_file)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    (output, modules)
+}
+
+#[test]
+fn inclusion_proofs() {
+    let dir = tempfile::tempdir().unwrap();
+    let (output, modules) = verify(
+        &fixture(),
+        dir.path(),
+        concat!(
+            r#"{"source":"github.com/BurntSushi/toml","version":"v1.3.0"}"#,
+            "\n",
+            r#"{"source":"example.com/last","versions":["v0.1.0"],"latest_release":"v0.1.0"}"#,
+            "\n",
+            r#"{"source":"example.com/newer","version":"v2.0.0"}"#,
+            "\n",
+            r#"{"source":"example.com/tampered","version":"v1.0.0"}"#,
+            "\n",
+            r#"{"source":"example.com/unknown","version":"v1.0.0"}"#,
+            "\n",
+        ),
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let verified: Vec<bool> = modules
+        .iter()
+        .map(|m| m["sumdb_verified"].as_bool().unwrap())
+        .collect();
+    assert_eq!(verified, [true, true, true, false, false]);
+
+    // Record 5 sits in the first full tile, record 299 in the partial one
+    assert_eq!(modules[0]["sumdb"]["record"], 5);
+    assert_eq!(modules[1]["sumdb"]["record"], 299);
+    assert!(modules[0]["sumdb"]["lines"][1]
+        .as_str()
+        .unwrap()
+        .starts_with("github.com/BurntSushi/toml v1.3.0/go.mod h1:"));
+    assert_eq!(modules[2]["sumdb"]["tree_size"], 301);
+
+    // A record the log does not hold at its position fails its proof
+    assert!(modules[3]["sumdb_error"]
+        .as_str()
+        .unwrap()
+        .contains("Inclusion proof"));
+    assert!(modules[4].get("sumdb_error").is_none());
+}
+
+#[test]
+fn forged_tree_head_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let sumdb = dir.path().join("sumdb");
+    std::fs::create_dir(&sumdb).unwrap();
+    let latest = std::fs::read_to_string(fixture().join("latest")).unwrap();
+    std::fs::write(
+        sumdb.join("latest"),
+        latest.replacen("\n300\n", "\n299\n", 1),
+    )
+    .unwrap();
+
+    let (output, _) = verify(
+        &sumdb,
+        dir.path(),
+        r#"{"source":"example.com/last","version":"v0.1.0"}"#,
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No valid signature"));
+}